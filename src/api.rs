@@ -0,0 +1,66 @@
+//! Server functions, reachable under `/api`
+#![allow(clippy::empty_structs_with_brackets)] // reason = "argument-less server functions generate them"
+use crate::gamedata::{
+    self, BoardRuns, CustomLevels, RunData, RunDetails, RunFilter, RunId, Runners, Track,
+};
+use leptos::{server, ServerFnError};
+#[cfg(feature = "ssr")]
+use std::sync::Arc;
+
+#[cfg(feature = "ssr")]
+pub mod v1;
+
+/// Parses the runs and custom levels from the current run data and runner registry
+#[cfg(feature = "ssr")]
+async fn run_data() -> Result<Arc<RunData>, gamedata::Error> {
+    gamedata::parse_runs(
+        &gamedata::load_run_data().await,
+        &gamedata::load_runners_data().await,
+    )
+}
+
+/// Lists the tracks with runs in leaderboard order, along with the custom levels
+#[server(ListTracks, "/api")]
+pub async fn list_tracks(
+) -> Result<Result<(Vec<Track>, CustomLevels), gamedata::Error>, ServerFnError> {
+    Ok(run_data()
+        .await
+        .map(|data| (data.tracks(), data.custom_levels.clone())))
+}
+
+/// Fetches the runs a board with the filter shows
+#[server(GetBoard, "/api")]
+pub async fn get_board(
+    filter: RunFilter,
+) -> Result<Result<BoardRuns, gamedata::Error>, ServerFnError> {
+    Ok(run_data().await.map(|data| data.board(&filter)))
+}
+
+/// Fetches a run along with the runs its page refers to and the runner registry
+#[server(GetRunDetails, "/api")]
+pub async fn get_run_details(
+    id: RunId,
+) -> Result<Result<Option<(RunDetails, Runners)>, gamedata::Error>, ServerFnError> {
+    let details = match run_data().await {
+        Ok(data) => data.details(id),
+        Err(e) => return Ok(Err(e)),
+    };
+    Ok(
+        gamedata::parse_runners(&gamedata::load_runners_data().await)
+            .map(|runners| details.map(|details| (details, runners))),
+    )
+}
+
+/// Lists the registered runners
+#[server(ListRunners, "/api")]
+pub async fn list_runners() -> Result<Result<Runners, gamedata::Error>, ServerFnError> {
+    Ok(gamedata::parse_runners(
+        &gamedata::load_runners_data().await,
+    ))
+}
+
+/// Fetches every run in leaderboard order, along with the custom levels they may be on
+#[server(GetRunData, "/api")]
+pub async fn get_run_data() -> Result<Result<RunData, gamedata::Error>, ServerFnError> {
+    Ok(run_data().await.map(|data| RunData::clone(&data)))
+}
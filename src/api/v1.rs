@@ -0,0 +1,248 @@
+//! Read-only JSON API under `/api/v1`, whose response shapes only change along with the version
+use crate::gamedata::{
+    self, Category, CustomLevels, Difficulty, DifficultyMode, Patch, Run, RunData, RunFilter,
+    RunId, Runner, Runners, Track,
+};
+use actix_web::{
+    get,
+    web::{self, Path, Query},
+    HttpResponse, Scope,
+};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+/// Page size used when the query does not specify one
+const DEFAULT_LIMIT: usize = 100;
+/// Largest page size a query can ask for
+const MAX_LIMIT: usize = 1000;
+
+#[derive(Serialize)]
+struct TrackJson {
+    /// One of `level`, `layer`, `act` or `fullgame`
+    kind: &'static str,
+    /// Identifier accepted by the `track` query parameter
+    slug: String,
+    name: String,
+}
+
+impl TrackJson {
+    fn new(track: &Track, custom_levels: &CustomLevels) -> Self {
+        Self {
+            kind: track.kind(),
+            slug: track.slug(),
+            name: custom_levels.track_name(track),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct PatchJson {
+    /// Identifier accepted by the `patch` query parameter
+    version: String,
+    name: String,
+    release_date: Option<String>,
+}
+
+impl From<&Patch> for PatchJson {
+    fn from(patch: &Patch) -> Self {
+        Self {
+            version: patch.version().to_owned(),
+            name: patch.to_string(),
+            release_date: patch.release_date().map(|d| d.to_string()),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct RunJson {
+    id: RunId,
+    /// Id of the layer, act or fullgame run this run is a segment of
+    parent: Option<RunId>,
+    /// Id of the runner, as listed by `/api/v1/runners/{name}`
+    runner: String,
+    track: TrackJson,
+    /// `Any`, or the modifiers joined by `+`, e.g. `P+NoMo`
+    category: String,
+    difficulty: Difficulty,
+    igt_ms: u32,
+    submission_date: String,
+    patch: PatchJson,
+    proof: String,
+}
+
+impl RunJson {
+    fn new(run: &Run, custom_levels: &CustomLevels) -> Self {
+        Self {
+            id: run.id(),
+            parent: run.parent,
+            runner: run.runner.clone(),
+            track: TrackJson::new(&run.track, custom_levels),
+            category: run.category.to_string(),
+            difficulty: run.difficulty,
+            igt_ms: run.igt_ms,
+            submission_date: run.submission_date.to_string(),
+            patch: (&run.patch_release_date).into(),
+            proof: run.proof.clone(),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct Page {
+    total: usize,
+    offset: usize,
+    limit: usize,
+    runs: Vec<RunJson>,
+}
+
+#[derive(Serialize)]
+struct ErrorJson {
+    error: String,
+}
+
+impl ErrorJson {
+    fn bad_request(error: String) -> HttpResponse {
+        HttpResponse::BadRequest().json(Self { error })
+    }
+}
+
+/// Loads the runs and the runners, or the response explaining why they could not be
+async fn load() -> Result<(Arc<RunData>, Runners), HttpResponse> {
+    let runners_data = gamedata::load_runners_data().await;
+    gamedata::parse_runs(&gamedata::load_run_data().await, &runners_data)
+        .and_then(|runs| Ok((runs, gamedata::parse_runners(&runners_data)?)))
+        .map_err(|e| {
+            HttpResponse::InternalServerError().json(ErrorJson {
+                error: e.to_string(),
+            })
+        })
+}
+
+#[derive(Deserialize)]
+struct RunsQuery {
+    /// Track slug, as listed by `/api/v1/tracks`
+    track: Option<String>,
+    /// `Any`, or the modifiers the runs must have joined by `+`, e.g. `P%2BNoMo`
+    category: Option<Category>,
+    difficulty: Option<Difficulty>,
+    /// `Exact` to only match the difficulty, `Minimum` to also match harder ones
+    #[serde(default)]
+    difficulty_mode: DifficultyMode,
+    /// Patch version label or release date
+    patch: Option<String>,
+    /// Runner id or any name the runner goes by
+    runner: Option<String>,
+    #[serde(default)]
+    offset: usize,
+    limit: Option<usize>,
+}
+
+/// Lists the runs matching the query, in leaderboard order
+#[get("/runs")]
+async fn list_runs(query: Query<RunsQuery>) -> HttpResponse {
+    let query = query.into_inner();
+    let (data, runners) = match load().await {
+        Ok(loaded) => loaded,
+        Err(response) => return response,
+    };
+    let track = match query.track.as_deref() {
+        Some(slug) => match Track::from_slug(slug, &data.custom_levels) {
+            Some(track) => Some(track),
+            None => return ErrorJson::bad_request(format!("Unknown track \"{slug}\"")),
+        },
+        None => None,
+    };
+    let patch = match query.patch.as_deref().map(Patch::resolve) {
+        Some(Patch::Unknown(label)) => {
+            return ErrorJson::bad_request(format!("Unknown patch \"{label}\""));
+        }
+        patch => patch,
+    };
+    let filter = RunFilter {
+        track,
+        category: query.category,
+        difficulty: query.difficulty,
+        difficulty_mode: query.difficulty_mode,
+        patch_from: patch.clone(),
+        patch_until: patch,
+        ..RunFilter::default()
+    };
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+    let runner = query
+        .runner
+        .as_deref()
+        .map(|name| runners.resolve(name).map_or(name, |r| &r.id));
+    let matching = data
+        .runs
+        .iter()
+        .filter(|r| filter.matches(r))
+        .filter(|r| runner.is_none_or(|id| id == r.runner))
+        .collect::<Vec<_>>();
+    HttpResponse::Ok().json(Page {
+        total: matching.len(),
+        offset: query.offset,
+        limit,
+        runs: matching
+            .into_iter()
+            .skip(query.offset)
+            .take(limit)
+            .map(|r| RunJson::new(r, &data.custom_levels))
+            .collect(),
+    })
+}
+
+/// Lists the tracks that have runs, in leaderboard order
+#[get("/tracks")]
+async fn list_tracks() -> HttpResponse {
+    let (data, _) = match load().await {
+        Ok(loaded) => loaded,
+        Err(response) => return response,
+    };
+    let mut tracks = data.runs.iter().map(|r| &r.track).collect::<Vec<_>>();
+    tracks.dedup();
+    HttpResponse::Ok().json(
+        tracks
+            .into_iter()
+            .map(|t| TrackJson::new(t, &data.custom_levels))
+            .collect::<Vec<_>>(),
+    )
+}
+
+#[derive(Serialize)]
+struct RunnerJson {
+    #[serde(flatten)]
+    runner: Runner,
+    runs: Vec<RunJson>,
+}
+
+/// Shows the runner along with every run of theirs, in leaderboard order
+#[get("/runners/{name}")]
+async fn get_runner(name: Path<String>) -> HttpResponse {
+    let name = name.into_inner();
+    let (data, runners) = match load().await {
+        Ok(loaded) => loaded,
+        Err(response) => return response,
+    };
+    let Some(runner) = runners.resolve(&name) else {
+        return HttpResponse::NotFound().json(ErrorJson {
+            error: format!("No runner goes by \"{name}\""),
+        });
+    };
+    HttpResponse::Ok().json(RunnerJson {
+        runner: runner.clone(),
+        runs: data
+            .runs
+            .iter()
+            .filter(|r| r.runner == runner.id)
+            .map(|r| RunJson::new(r, &data.custom_levels))
+            .collect(),
+    })
+}
+
+/// The `/api/v1` routes, to be registered before the server functions' catch-all `/api` route
+pub fn service() -> Scope {
+    web::scope("/api/v1")
+        .service(list_runs)
+        .service(list_tracks)
+        .service(get_runner)
+}
@@ -3,10 +3,10 @@
     clippy::infinite_loop,
     clippy::module_name_repetitions
 )] // reason = "Leptos components do not appropriately allow or expect lints"
-use crate::components::Leaderboard;
+use crate::components::{Leaderboard, RunBreakdown, RunnerProfile, TrackLeaderboard};
 use leptos::{component, view, IntoView};
 use leptos_meta::{provide_meta_context, Stylesheet, Title};
-use leptos_router::{Route, Router, Routes};
+use leptos_router::{Route, Router, Routes, SsrMode};
 
 #[cfg(debug_assertions)]
 use leptos::tracing;
@@ -25,7 +25,10 @@ pub fn App() -> impl IntoView {
                 href="https://fonts.googleapis.com/css2?family=Inter:wght@300;500;700&display=swap"
                 rel="stylesheet"
             />
-            <HomePage/>
+            // no routes on ghpages, the router only keeps the leaderboard state in the URL
+            <Router>
+                <HomePage/>
+            </Router>
         }
     } else {
         view! {
@@ -43,7 +46,11 @@ pub fn App() -> impl IntoView {
                 <main>
                     <Routes>
                         <Route path="" view=HomePage/>
-                        <Route path="/lb" view=Leaderboard/>
+                        <Route path="/lb" view=|| view! { <Leaderboard/> }/>
+                        // async, so that undeclared custom levels still respond with a 404
+                        <Route path="/lb/:track/:category" view=TrackLeaderboard ssr=SsrMode::Async/>
+                        <Route path="/run/:id" view=RunBreakdown/>
+                        <Route path="/runner/:id" view=RunnerProfile/>
                         <Route path="/*any" view=NotFound/>
                     </Routes>
                 </main>
@@ -63,7 +70,7 @@ fn HomePage() -> impl IntoView {
 
 /// 404 - Not Found
 #[component]
-fn NotFound() -> impl IntoView {
+pub fn NotFound() -> impl IntoView {
     // set an HTTP status code 404
     // this is feature gated because it can only be done during
     // initial server-side rendering
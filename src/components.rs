@@ -3,6 +3,201 @@
     clippy::infinite_loop,
     clippy::module_name_repetitions
 )] // reason = "Leptos components do not appropriately allow or expect lints"
+#[cfg(not(feature = "csr"))]
+use crate::api;
+use crate::gamedata::{
+    self, BoardRuns, CustomLevels, RunData, RunDetails, RunFilter, RunId, Runner, Runners, Track,
+};
+use cfg_if::cfg_if;
+#[cfg(not(feature = "csr"))]
+use leptos::ServerFnError;
+use leptos::{view, CollectView, IntoView, View};
+use std::cmp;
 
 mod leaderboard;
-pub use leaderboard::Leaderboard;
+pub use leaderboard::{Leaderboard, TrackLeaderboard};
+mod run;
+pub use run::RunBreakdown;
+mod runner;
+pub use runner::RunnerProfile;
+
+#[cfg(not(feature = "csr"))]
+#[allow(clippy::needless_pass_by_value)] // reason = "used as a `map_err` argument"
+fn unavailable(error: ServerFnError) -> gamedata::Error {
+    gamedata::Error::Unavailable(error.to_string())
+}
+
+/// Fetches the tracks with runs along with the custom levels and the runner registry, through the
+/// server functions or, without a server, from the data files
+#[cfg_attr(feature = "csr", allow(clippy::future_not_send))] // reason = "the browser is single threaded"
+async fn fetch_board_setup() -> Result<(Vec<Track>, CustomLevels, Runners), gamedata::Error> {
+    cfg_if! {
+    if #[cfg(feature = "csr")] {
+        let data = gamedata::parse_runs(
+            &gamedata::load_run_data().await,
+            &gamedata::load_runners_data().await,
+        )?;
+        Ok((data.tracks(), data.custom_levels.clone(), fetch_runners().await?))
+    } else {
+        let (tracks, custom_levels) = api::list_tracks().await.map_err(unavailable)??;
+        Ok((tracks, custom_levels, fetch_runners().await?))
+    }
+    }
+}
+
+/// Fetches the runs a board with the filter shows
+#[cfg_attr(feature = "csr", allow(clippy::future_not_send))] // reason = "the browser is single threaded"
+async fn fetch_board(filter: RunFilter) -> Result<BoardRuns, gamedata::Error> {
+    cfg_if! {
+    if #[cfg(feature = "csr")] {
+        let data = gamedata::parse_runs(
+            &gamedata::load_run_data().await,
+            &gamedata::load_runners_data().await,
+        )?;
+        Ok(data.board(&filter))
+    } else {
+        api::get_board(filter).await.map_err(unavailable)?
+    }
+    }
+}
+
+/// Fetches a run along with the runs its page refers to and the runner registry
+#[cfg_attr(feature = "csr", allow(clippy::future_not_send))] // reason = "the browser is single threaded"
+async fn fetch_run_details(id: RunId) -> Result<Option<(RunDetails, Runners)>, gamedata::Error> {
+    cfg_if! {
+    if #[cfg(feature = "csr")] {
+        let data = gamedata::parse_runs(
+            &gamedata::load_run_data().await,
+            &gamedata::load_runners_data().await,
+        )?;
+        Ok(match data.details(id) {
+            Some(details) => Some((details, fetch_runners().await?)),
+            None => None,
+        })
+    } else {
+        api::get_run_details(id).await.map_err(unavailable)?
+    }
+    }
+}
+
+/// Fetches the runner registry
+#[cfg_attr(feature = "csr", allow(clippy::future_not_send))] // reason = "the browser is single threaded"
+async fn fetch_runners() -> Result<Runners, gamedata::Error> {
+    cfg_if! {
+    if #[cfg(feature = "csr")] {
+        gamedata::parse_runners(&gamedata::load_runners_data().await)
+    } else {
+        api::list_runners().await.map_err(unavailable)?
+    }
+    }
+}
+
+/// Fetches every run along with the custom levels and the runner registry
+#[cfg_attr(feature = "csr", allow(clippy::future_not_send))] // reason = "the browser is single threaded"
+async fn fetch_runs() -> Result<(RunData, Runners), gamedata::Error> {
+    cfg_if! {
+    if #[cfg(feature = "csr")] {
+        let data = gamedata::parse_runs(
+            &gamedata::load_run_data().await,
+            &gamedata::load_runners_data().await,
+        )?;
+        Ok((RunData::clone(&data), fetch_runners().await?))
+    } else {
+        Ok((
+            api::get_run_data().await.map_err(unavailable)??,
+            fetch_runners().await?,
+        ))
+    }
+    }
+}
+
+fn error_into_view(error: &gamedata::Error) -> View {
+    use gamedata::Error;
+    match error {
+        Error::Syntax {
+            file,
+            message,
+            position,
+        } => view! {
+            <p class="error">
+                <code>{file.clone()}</code> " is malformed" {position.map(|p| format!(" at {p}"))}
+                ": " {message.clone()}
+            </p>
+        }
+        .into_view(),
+        Error::MissingRuns => view! {
+            <p class="error">"The run data lacks a " <code>"[[runs]]"</code> " table"</p>
+        }
+        .into_view(),
+        Error::NoRuns => {
+            view! { <p class="error">"No runs have been submitted yet"</p> }.into_view()
+        }
+        Error::Invalid(errors) => view! {
+            <div class="error">
+                <p>{errors.len()} " runs break the submission rules:"</p>
+                <ul>
+                    {errors.iter().map(|e| view! { <li>{e.to_string()}</li> }).collect_view()}
+                </ul>
+            </div>
+        }
+        .into_view(),
+        Error::AmbiguousRunners(collisions) => view! {
+            <div class="error">
+                <p>"Some names of " <code>"runners.toml"</code> " are used by several runners:"</p>
+                <ul>
+                    {collisions.iter().map(|c| view! { <li>{c.to_string()}</li> }).collect_view()}
+                </ul>
+            </div>
+        }
+        .into_view(),
+        Error::InvalidCustomLevels(errors) => view! {
+            <div class="error">
+                <p>"Some custom levels of " <code>"run_data.toml"</code> " have invalid ids:"</p>
+                <ul>{errors.iter().map(|e| view! { <li>{e.to_string()}</li> }).collect_view()}</ul>
+            </div>
+        }
+        .into_view(),
+        Error::Unavailable(message) => view! {
+            <p class="error">"Could not reach the server, try reloading the page: " {message.clone()}</p>
+        }
+        .into_view(),
+    }
+}
+
+/// Flag and name of the runner, linking to their page
+fn runner_into_view(id: &str, runners: &Runners) -> View {
+    let runner = runners.get(id);
+    // ghpages has no router to serve the runner pages
+    let link = if cfg!(feature = "ghpages") {
+        runner
+            .and_then(|r| r.links.speedrun.clone())
+            .unwrap_or_else(|| format!("https://www.speedrun.com/users/{id}"))
+    } else {
+        format!("/runner/{id}")
+    };
+    view! {
+        {runner.and_then(Runner::flag).map(|flag| view! { <span class="flag">{flag}</span> })}
+        <a href=link>{runners.name(id).to_owned()}</a>
+    }
+    .into_view()
+}
+
+/// Formats an in-game time as `mm:ss:mmm`
+fn format_igt(igt_ms: u32) -> String {
+    format!(
+        "{:02}:{:02}:{:03}",
+        igt_ms / 1000 / 60,
+        igt_ms / 1000 % 60,
+        igt_ms % 1000
+    )
+}
+
+/// Formats how far a time is behind, or ahead of, another time as signed seconds
+fn format_gap(igt_ms: u64, reference_ms: u64) -> String {
+    let (sign, gap) = match igt_ms.cmp(&reference_ms) {
+        cmp::Ordering::Less => ("-", reference_ms - igt_ms),
+        cmp::Ordering::Equal => ("±", 0),
+        cmp::Ordering::Greater => ("+", igt_ms - reference_ms),
+    };
+    format!("{sign}{}.{:03}", gap / 1000, gap % 1000)
+}
@@ -1,49 +1,314 @@
-use crate::gamedata::{self, Category, Run, Track};
+use super::{
+    error_into_view, fetch_board, fetch_board_setup, format_gap, format_igt, runner_into_view,
+};
+use crate::app::NotFound;
+use crate::gamedata::{
+    self, BoardRuns, Category, CustomLevels, Difficulty, DifficultyMode, Modifier, Patch, Run,
+    RunFilter, Runners, Track,
+};
 use leptos::{
-    component, create_signal, event_target_value,
+    component, create_memo, create_resource, event_target_value,
     html::{Option_, Tr},
-    view, with, HtmlElement, IntoAttribute, IntoClass, IntoView, SignalGet, SignalSet, SignalWith,
+    view, CollectView, HtmlElement, IntoAttribute, IntoClass, IntoView, Memo, Signal, SignalGet,
+    SignalGetUntracked, SignalSetter, SignalWith, SignalWithUntracked, Suspense, Transition, View,
+};
+use leptos_meta::Title;
+use leptos_router::{
+    use_location, use_navigate, use_params_map, use_query_map, NavigateOptions, ParamsMap,
 };
+use std::{cmp, iter, sync::Arc};
+use toml::value::{Date, Datetime};
 
 #[cfg(debug_assertions)]
 use leptos::leptos_dom::tracing;
 
-// TODO:
-// 1. Add options to filter by:
-// - run obsoletion
-// - difficulty
-// - date
-// - patch
-// 2. Add alternative sorting options:
-// - date
-// - release date
+/// Board of every track, or with `routed` of the track and category in the `/lb/:track/:category`
+/// path
 #[component]
-pub fn Leaderboard() -> impl IntoView {
-    let runs = {
-        let mut runs = match gamedata::load_runs() {
-            Ok(v) => v,
-            Err(e) => {
-                return view! {
-                    <div class="leaderboard">
-                        <p class="error">"Failed to leaderboard with error: " {e.to_string()}</p>
-                    </div>
-                };
+pub fn Leaderboard(#[prop(optional)] routed: bool) -> impl IntoView {
+    let setup = create_resource(|| (), |()| fetch_board_setup());
+    let params = use_params_map();
+    // custom levels can only be told apart from unknown tracks once they are loaded
+    let route = create_memo(move |_| {
+        setup.with(|setup| {
+            let custom_levels = &setup.as_ref()?.as_ref().ok()?.1;
+            params.with(|p| {
+                Some((
+                    Track::from_slug(p.get("track")?, custom_levels)?,
+                    Category::from_slug(p.get("category")?)?,
+                ))
+            })
+        })
+    });
+    // only switching between a board and a 404 rerenders, so that the board keeps its state
+    let found = create_memo(move |_| !routed || route.with(Option::is_some));
+    let title = move || {
+        setup
+            .with(|setup| {
+                let custom_levels = &setup.as_ref()?.as_ref().ok()?.1;
+                route.with(|route| {
+                    let (track, category) = route.as_ref()?;
+                    Some(format!(
+                        "{} {category} - UKND LB",
+                        custom_levels.track_name(track)
+                    ))
+                })
+            })
+            .unwrap_or_default()
+    };
+    view! {
+        <Suspense fallback=|| {
+            view! {
+                <div class="leaderboard">
+                    <p class="loading">"Loading runs..."</p>
+                </div>
             }
-        };
-        runs.sort_by(|a, b| {
-            a.track
-                .cmp(&b.track)
-                .then(a.igt_ms.cmp(&b.igt_ms))
-                .then(b.difficulty.cmp(&a.difficulty))
-                .then(b.patch_release_date.cmp(&a.patch_release_date))
-                .then(a.submission_date.cmp(&b.submission_date))
-                .then(a.runner.cmp(&b.runner))
-                .then_with(|| panic!("Duplicate runs {a:?} {b:?}"))
+        }>
+            {move || {
+                setup
+                    .get()
+                    .map(|setup| match setup {
+                        Ok(_) if !found.get() => view! { <NotFound/> }.into_view(),
+                        Ok((tracks, custom_levels, runners)) => {
+                            view! {
+                                {routed.then(|| view! { <Title text=title/> })}
+                                <Board
+                                    tracks=tracks.into()
+                                    runners=Arc::new(runners)
+                                    custom_levels=Arc::new(custom_levels)
+                                    routed=routed
+                                />
+                            }
+                                .into_view()
+                        }
+                        Err(e) => view! { <div class="leaderboard">{error_into_view(&e)}</div> }.into_view(),
+                    })
+            }}
+
+        </Suspense>
+    }
+}
+
+/// Board of a single track and category at `/lb/:track/:category`, or a 404 for unknown slugs
+///
+/// The slugs of custom levels only resolve once those are loaded, so the route should be
+/// rendered asynchronously for the 404 status to reach the response.
+#[component]
+pub fn TrackLeaderboard() -> impl IntoView {
+    view! { <Leaderboard routed=true/> }
+}
+
+/// Read and write halves of a signal
+type SignalPair<T> = (Signal<T>, SignalSetter<T>);
+
+/// Signal kept in the query parameter `key`, which is left out of the URL while it holds `default`
+fn query_signal<T: Clone + PartialEq + 'static>(
+    key: &'static str,
+    default: T,
+    parse: impl Fn(&str) -> Option<T> + 'static,
+    format: fn(&T) -> String,
+) -> SignalPair<T> {
+    let query = use_query_map();
+    let location = use_location();
+    let navigate = use_navigate();
+    let read = {
+        let default = default.clone();
+        create_memo(move |_| {
+            query
+                .with(|q| q.get(key).and_then(|value| parse(value)))
+                .unwrap_or_else(|| default.clone())
+        })
+    };
+    let write = SignalSetter::map(move |value: T| {
+        let mut query = query.get_untracked();
+        drop(if value == default {
+            query.remove(key)
+        } else {
+            query.insert(key.to_owned(), format(&value))
         });
-        runs
+        navigate(
+            &format!(
+                "{}{}",
+                location.pathname.get_untracked(),
+                query.to_query_string()
+            ),
+            keep_scroll(),
+        );
+    });
+    (read.into(), write)
+}
+
+/// Track and category signals kept in the `/lb/:track/:category` path, along with the query
+fn path_signals(
+    default_track: Track,
+    custom_levels: Arc<CustomLevels>,
+) -> (SignalPair<Track>, SignalPair<Category>) {
+    let params = use_params_map();
+    let query = use_query_map();
+    let track = create_memo(move |_| {
+        params
+            .with(|p| {
+                p.get("track")
+                    .and_then(|slug| Track::from_slug(slug, &custom_levels))
+            })
+            .unwrap_or_else(|| default_track.clone())
+    });
+    let category = create_memo(move |_| {
+        params
+            .with(|p| p.get("category").and_then(|slug| Category::from_slug(slug)))
+            .unwrap_or_default()
+    });
+    let path = move |track: &Track, category: Category| {
+        format!(
+            "/lb/{}/{}{}",
+            track.slug(),
+            category.slug(),
+            query.with_untracked(ParamsMap::to_query_string)
+        )
+    };
+    let track_w = {
+        let navigate = use_navigate();
+        SignalSetter::map(move |t: Track| {
+            navigate(&path(&t, category.get_untracked()), keep_scroll());
+        })
+    };
+    let category_w = {
+        let navigate = use_navigate();
+        SignalSetter::map(move |c: Category| {
+            navigate(&track.with_untracked(|t| path(t, c)), keep_scroll());
+        })
     };
-    let (category_r, category_w) = create_signal(Category::Any);
-    let (track_r, track_w) = create_signal(runs[0].track.to_string());
+    ((track.into(), track_w), (category.into(), category_w))
+}
+
+/// Navigation options of the controls, which should not scroll back to the top
+fn keep_scroll() -> NavigateOptions {
+    NavigateOptions {
+        scroll: false,
+        ..NavigateOptions::default()
+    }
+}
+
+/// State of the board controls, kept in the query parameters so that links reopen the same board
+#[derive(Clone, Copy)]
+struct Controls {
+    track: SignalPair<Track>,
+    category: SignalPair<Category>,
+    difficulty: SignalPair<Difficulty>,
+    difficulty_mode: SignalPair<DifficultyMode>,
+    /// First and last submission dates
+    submitted: [SignalPair<Option<Date>>; 2],
+    /// First and last patches
+    patches: [SignalPair<Option<Patch>>; 2],
+    show_obsolete: SignalPair<bool>,
+}
+
+impl Controls {
+    /// Reads the controls from the query, or the track and category from the path if `routed`
+    fn from_query(default_track: Track, custom_levels: Arc<CustomLevels>, routed: bool) -> Self {
+        let date = |value: &str| {
+            value
+                .parse::<Datetime>()
+                .ok()
+                .and_then(|d| d.date)
+                .map(Some)
+        };
+        let date_param = |date: &Option<Date>| date.map(|d| d.to_string()).unwrap_or_default();
+        let patch = |version: &str| Some(Some(Patch::resolve(version)));
+        let patch_param = |patch: &Option<Patch>| {
+            patch
+                .as_ref()
+                .map(|p| p.version().to_owned())
+                .unwrap_or_default()
+        };
+        let (track, category) = if routed {
+            path_signals(default_track, custom_levels)
+        } else {
+            (
+                query_signal(
+                    "track",
+                    default_track,
+                    move |slug| Track::from_slug(slug, &custom_levels),
+                    Track::slug,
+                ),
+                query_signal("category", Category::default(), Category::from_slug, |c| {
+                    c.slug()
+                }),
+            )
+        };
+        Self {
+            track,
+            category,
+            difficulty: query_signal(
+                "difficulty",
+                Difficulty::Violent,
+                Difficulty::from_slug,
+                |d| d.slug().to_owned(),
+            ),
+            difficulty_mode: query_signal(
+                "mode",
+                DifficultyMode::Exact,
+                |mode| match mode {
+                    "exact" => Some(DifficultyMode::Exact),
+                    "minimum" => Some(DifficultyMode::Minimum),
+                    _ => None,
+                },
+                |mode| {
+                    match mode {
+                        DifficultyMode::Exact => "exact",
+                        DifficultyMode::Minimum => "minimum",
+                    }
+                    .to_owned()
+                },
+            ),
+            submitted: [
+                query_signal("from", None, date, date_param),
+                query_signal("until", None, date, date_param),
+            ],
+            patches: [
+                query_signal("patch_from", None, patch, patch_param),
+                query_signal("patch_until", None, patch, patch_param),
+            ],
+            show_obsolete: query_signal(
+                "obsolete",
+                false,
+                |value| value.parse().ok(),
+                ToString::to_string,
+            ),
+        }
+    }
+
+    fn filter(self) -> Memo<RunFilter> {
+        let [submitted_from, submitted_until] = self.submitted.map(|(date_r, _)| date_r);
+        let [patch_from, patch_until] = self.patches.map(|(patch_r, _)| patch_r);
+        create_memo(move |_| RunFilter {
+            track: Some(self.track.0.get()),
+            category: Some(self.category.0.get()),
+            difficulty: Some(self.difficulty.0.get()),
+            difficulty_mode: self.difficulty_mode.0.get(),
+            patch_from: patch_from.get(),
+            patch_until: patch_until.get(),
+            submitted_from: submitted_from.get(),
+            submitted_until: submitted_until.get(),
+            parent: None,
+        })
+    }
+}
+
+#[component]
+fn Board(
+    tracks: Arc<[Track]>,
+    runners: Arc<Runners>,
+    custom_levels: Arc<CustomLevels>,
+    routed: bool,
+) -> impl IntoView {
+    let controls = Controls::from_query(tracks[0].clone(), Arc::clone(&custom_levels), routed);
+    let filter = controls.filter();
+    let board = create_resource(move || filter.get(), fetch_board);
+    let (track_r, track_w) = controls.track;
+    let (show_obsolete_r, show_obsolete_w) = controls.show_obsolete;
+    let query = use_query_map();
+    let sort = create_memo(move |_| query.with(Sort::from_query));
     // INFO: need to call `into_class` manually to silence `unused_import` warning
     let _silencer = true.into_class();
     view! {
@@ -51,107 +316,445 @@ pub fn Leaderboard() -> impl IntoView {
             <div class="controls">
                 <div>
                     <div>
-                        <select on:change=move |ev| {
-                            track_w.set(event_target_value(&ev));
+                        <select on:change={
+                            let custom_levels = Arc::clone(&custom_levels);
+                            move |ev| {
+                                if let Some(track) = Track::from_slug(
+                                    &event_target_value(&ev),
+                                    &custom_levels,
+                                ) {
+                                    track_w.set(track);
+                                }
+                            }
                         }>
 
                             {
-                                let runs = runs.clone();
+                                let custom_levels = Arc::clone(&custom_levels);
                                 move || tracks_into_options(
-                                    runs.iter().map(|r| r.track.clone()).collect::<Vec<_>>(),
+                                    tracks.to_vec(),
                                     &track_r.get(),
+                                    &custom_levels,
                                 )
                             }
 
                         </select>
                     </div>
+                    {difficulty_controls(controls.difficulty, controls.difficulty_mode)}
+                    {category_controls(controls.category)}
+                    {range_controls(controls.submitted, controls.patches)}
                     <div>
                         <button
-                            on:click=move |_| category_w.set(Category::Any)
-                            class:selected=move || category_r.with(|c| *c == Category::Any)
-                        >
-                            "Any%"
-                        </button>
-                        <button
-                            on:click=move |_| category_w.set(Category::P)
-                            class:selected=move || category_r.with(|c| *c == Category::P)
-                        >
-                            "P Rank"
-                        </button>
-                        <button
-                            on:click=move |_| category_w.set(Category::NoMo)
-                            class:selected=move || category_r.with(|c| *c == Category::NoMo)
+                            on:click=move |_| show_obsolete_w.set(!show_obsolete_r.get())
+                            class:selected=show_obsolete_r
                         >
-                            "NoMo"
+                            "Show obsolete"
                         </button>
                     </div>
                 </div>
             </div>
-            <table>
-                <thead>
-                    <tr>
-                        <th>"#"</th>
-                        <th>"Player"</th>
-                        <th>"IGT"</th>
-                        <th>"Date"</th>
-                        <th>"Difficulty"</th>
-                        <th>"Patch"</th>
-                    </tr>
-                </thead>
-                <tbody>
-
-                    {move || runs_into_trs(
-                        &runs
-                            .iter()
-                            .filter(|r| {
-                                with!(
-                                    | track_r, category_r | * track_r == r.track.to_string() && (r
-                                    .category == * category_r || * category_r == Category::Any && r
-                                    .category == Category::P)
-                                )
+            <Transition fallback=|| {
+                view! { <p class="loading">"Loading runs..."</p> }
+            }>
+                {
+                    let runners = Arc::clone(&runners);
+                    let custom_levels = Arc::clone(&custom_levels);
+                    move || {
+                        board
+                            .get()
+                            .map(|board| match board {
+                                Ok(board) => {
+                                    let mut rows = rank_runs(
+                                        &board.runs.iter().collect::<Box<_>>(),
+                                        show_obsolete_r.get(),
+                                    );
+                                    sort.get().apply(&mut rows, &runners);
+                                    let trs = runs_into_trs(&board.parents, &runners, &custom_levels, &rows);
+                                    view! {
+                                        {sum_of_best_into_view(&board)}
+                                        <table>
+                                            <thead>
+                                                <tr>
+                                                    <th>"#"</th>
+                                                    {header_into_view("Player", SortKey::Runner, sort, query)}
+                                                    {header_into_view("IGT", SortKey::Igt, sort, query)}
+                                                    {header_into_view("Date", SortKey::Date, sort, query)}
+                                                    {header_into_view("Difficulty", SortKey::Difficulty, sort, query)}
+                                                    {header_into_view("Patch", SortKey::Patch, sort, query)}
+                                                </tr>
+                                            </thead>
+                                            <tbody>{trs}</tbody>
+                                        </table>
+                                    }
+                                        .into_view()
+                                }
+                                Err(e) => error_into_view(&e),
                             })
-                            .cloned()
-                            .collect::<Box<_>>(),
-                    )}
+                    }
+                }
+
+            </Transition>
+        </div>
+    }
+}
+
+/// Buttons picking the category of the board, Any% clears the modifiers which the others toggle
+fn category_controls((category_r, category_w): SignalPair<Category>) -> impl IntoView {
+    let modifiers = Modifier::ALL
+        .iter()
+        .map(|&modifier| {
+            view! {
+                <button
+                    on:click=move |_| category_w.set(category_r.get().toggled(modifier))
+                    class:selected=move || category_r.with(|c| c.has(modifier))
+                >
+                    {match modifier {
+                        Modifier::P => "P Rank",
+                        Modifier::NoMo => "NoMo",
+                    }}
+
+                </button>
+            }
+        })
+        .collect_view();
+    view! {
+        <div>
+            <button
+                on:click=move |_| category_w.set(Category::default())
+                class:selected=move || category_r.with(|c| c.modifiers().next().is_none())
+            >
+                "Any%"
+            </button>
+            {modifiers}
+        </div>
+    }
+}
+
+/// Difficulty select along with the buttons picking how it matches runs
+fn difficulty_controls(
+    (difficulty_r, difficulty_w): SignalPair<Difficulty>,
+    (difficulty_mode_r, difficulty_mode_w): SignalPair<DifficultyMode>,
+) -> impl IntoView {
+    view! {
+        <div>
+            <select on:change=move |ev| {
+                if let Some(difficulty) = event_target_value(&ev)
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|idx| Difficulty::ALL.get(idx))
+                {
+                    difficulty_w.set(*difficulty);
+                }
+            }>
 
-                </tbody>
-            </table>
+                {move || difficulties_into_options(difficulty_r.get())}
+
+            </select>
+            <button
+                on:click=move |_| difficulty_mode_w.set(DifficultyMode::Exact)
+                class:selected=move || {
+                    difficulty_mode_r.with(|m| *m == DifficultyMode::Exact)
+                }
+            >
+                "Exact"
+            </button>
+            <button
+                on:click=move |_| difficulty_mode_w.set(DifficultyMode::Minimum)
+                class:selected=move || {
+                    difficulty_mode_r.with(|m| *m == DifficultyMode::Minimum)
+                }
+            >
+                "Or harder"
+            </button>
+        </div>
+    }
+}
+
+/// Inputs restricting the board to the runs submitted and played within ranges, each given as the
+/// signals of its first and last included values
+fn range_controls(
+    dates: [SignalPair<Option<Date>>; 2],
+    patches: [SignalPair<Option<Patch>>; 2],
+) -> impl IntoView {
+    let [date_from, date_until] = dates.map(|(date_r, date_w)| {
+        view! {
+            <input
+                type="date"
+                // the attribute for server rendering, the property once edited
+                value=move || date_r.get().map(|d| d.to_string())
+                prop:value=move || date_r.get().map(|d| d.to_string()).unwrap_or_default()
+                on:change=move |ev| {
+                    date_w.set(event_target_value(&ev).parse::<Datetime>().ok().and_then(|d| d.date));
+                }
+            />
+        }
+    });
+    let [patch_from, patch_until] = patches.map(|(patch_r, patch_w)| {
+        view! {
+            <select on:change=move |ev| {
+                let version = event_target_value(&ev);
+                patch_w.set((!version.is_empty()).then(|| Patch::resolve(&version)));
+            }>
+
+                {move || patches_into_options(patch_r.get().as_ref())}
+
+            </select>
+        }
+    });
+    view! {
+        <div>
+            <span>"Submitted from"</span>
+            {date_from}
+            <span>"to"</span>
+            {date_until}
+        </div>
+        <div>
+            <span>"Played from"</span>
+            {patch_from}
+            <span>"to"</span>
+            {patch_until}
         </div>
     }
 }
 
-fn tracks_into_options(mut tracks: Vec<Track>, track: &str) -> Vec<HtmlElement<Option_>> {
-    use std::string;
+fn patches_into_options(selected: Option<&Patch>) -> Vec<HtmlElement<Option_>> {
+    iter::once(None)
+        .chain(Patch::all().into_iter().map(Some))
+        .map(|patch| {
+            let is_selected = patch.as_ref() == selected;
+            view! {
+                <option
+                    value=patch.as_ref().map_or("", Patch::version).to_owned()
+                    selected=is_selected
+                >
+                    {patch.map_or_else(|| String::from("any patch"), |p| p.to_string())}
+                </option>
+            }
+        })
+        .collect()
+}
+
+/// Sum of best of the board's track next to its record
+fn sum_of_best_into_view(board: &BoardRuns) -> Option<View> {
+    // runs are sorted by time, so the first run holds the record
+    let record = board.runs.first()?;
+    if record.track.segments().is_empty() {
+        return None;
+    }
+    let sum = board.sum_of_best?;
+    Some(
+        view! {
+            <div class="sum-of-best">
+                <p>
+                    "Sum of best: " <b>{format_igt(u32::try_from(sum).unwrap_or(u32::MAX))}</b>
+                    ", record " {format_igt(record.igt_ms)} " is "
+                    {format_gap(u64::from(record.igt_ms), sum)} " behind"
+                </p>
+            </div>
+        }
+        .into_view(),
+    )
+}
+
+fn difficulties_into_options(selected: Difficulty) -> Vec<HtmlElement<Option_>> {
+    Difficulty::ALL
+        .iter()
+        .enumerate()
+        .map(|(idx, difficulty)| {
+            view! {
+                <option value=idx.to_string() selected=*difficulty == selected>
+                    {difficulty.to_string()}
+                </option>
+            }
+        })
+        .collect()
+}
+
+/// Column the table can be sorted by
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+enum SortKey {
+    #[default]
+    Igt,
+    Runner,
+    Date,
+    Difficulty,
+    Patch,
+}
+
+impl SortKey {
+    const ALL: &'static [Self] = &[
+        Self::Igt,
+        Self::Runner,
+        Self::Date,
+        Self::Difficulty,
+        Self::Patch,
+    ];
+
+    /// Value of the `sort` query parameter
+    const fn param(self) -> &'static str {
+        match self {
+            Self::Igt => "igt",
+            Self::Runner => "runner",
+            Self::Date => "date",
+            Self::Difficulty => "difficulty",
+            Self::Patch => "patch",
+        }
+    }
+
+    fn compare(self, a: &Run, b: &Run, runners: &Runners) -> cmp::Ordering {
+        match self {
+            Self::Igt => a.igt_ms.cmp(&b.igt_ms),
+            Self::Runner => runners
+                .name(&a.runner)
+                .to_lowercase()
+                .cmp(&runners.name(&b.runner).to_lowercase()),
+            Self::Date => a.submission_date.cmp(&b.submission_date),
+            Self::Difficulty => a.difficulty.cmp(&b.difficulty),
+            Self::Patch => a.patch_release_date.cmp(&b.patch_release_date),
+        }
+    }
+}
+
+/// Order of the table, kept in the `sort` and `order` query parameters
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Sort {
+    key: SortKey,
+    descending: bool,
+}
+
+impl Sort {
+    fn from_query(query: &ParamsMap) -> Self {
+        Self {
+            key: query
+                .get("sort")
+                .and_then(|sort| SortKey::ALL.iter().find(|k| k.param() == sort))
+                .copied()
+                .unwrap_or_default(),
+            descending: query.get("order").is_some_and(|order| order == "desc"),
+        }
+    }
+
+    /// The current query along with this order
+    fn href(self, query: &ParamsMap) -> String {
+        let mut query = query.clone();
+        drop(query.insert("sort".to_owned(), self.key.param().to_owned()));
+        drop(query.insert(
+            "order".to_owned(),
+            (if self.descending { "desc" } else { "asc" }).to_owned(),
+        ));
+        query.to_query_string()
+    }
+
+    /// Order a click on the header of the key leads to, flipping the direction if already sorted
+    /// by it
+    fn toggled(self, key: SortKey) -> Self {
+        Self {
+            key,
+            descending: self.key == key && !self.descending,
+        }
+    }
+
+    /// Sorts the ranked rows, whose ranks keep following the IGT
+    fn apply(self, rows: &mut [(Option<usize>, Run)], runners: &Runners) {
+        rows.sort_by(|(_, a), (_, b)| {
+            let ordering = self.key.compare(a, b, runners);
+            if self.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+    }
+}
+
+/// Header linking to the table sorted by its column
+fn header_into_view(
+    label: &'static str,
+    key: SortKey,
+    sort: Memo<Sort>,
+    query: Memo<ParamsMap>,
+) -> impl IntoView {
+    let arrow = move || {
+        sort.with(|sort| (sort.key == key).then_some(if sort.descending { " ▼" } else { " ▲" }))
+    };
+    view! {
+        <th>
+            <a href=move || query.with(|q| sort.get().toggled(key).href(q)) noscroll=true>
+                {label}
+                {arrow}
+            </a>
+        </th>
+    }
+}
+
+/// Ranks the runs of a board, leaving the obsolete ones unranked or out altogether
+fn rank_runs(board: &[&Run], show_obsolete: bool) -> Vec<(Option<usize>, Run)> {
+    let mut rank = 0;
+    board
+        .iter()
+        .zip(gamedata::obsolete(board.iter().copied()))
+        .filter(|&(_, obsolete)| show_obsolete || !obsolete)
+        .map(|(run, obsolete)| {
+            let run_rank = (!obsolete).then(|| {
+                rank += 1;
+                rank
+            });
+            (run_rank, (*run).clone())
+        })
+        .collect()
+}
+
+/// Options of the tracks with runs, the custom levels being grouped apart in declaration order
+fn tracks_into_options(
+    mut tracks: Vec<Track>,
+    track: &Track,
+    custom_levels: &CustomLevels,
+) -> impl IntoView {
     tracks.sort_by(|a, b| b.shallow_cmp(a)); // method chaining be in shambles rn
     tracks.dedup();
-    tracks
+    let (custom, official) = tracks.into_iter().partition::<Vec<_>, _>(Track::is_custom);
+    let official = official
         .iter()
-        .map(string::ToString::to_string)
         .map(|t| {
-            let trk = t.clone();
-            let track = track.to_owned();
-            // NOTE:
-            // track.with(|track| *track ...)
-            // differs from
-            // track.with(|&track| track ...)
-            // for `move` closures
             view! {
-                <option value=t.clone() selected=move || track == trk>
-                    {t}
+                <option value=t.slug() selected=t == track>
+                    {t.to_string()}
                 </option>
             }
         })
-        .collect()
+        .collect_view();
+    let custom = custom_levels
+        .iter()
+        .map(|level| (level.track(), level))
+        .filter(|(t, _)| custom.contains(t))
+        .map(|(t, level)| {
+            view! {
+                <option value=t.slug() selected=t == *track>
+                    {format!("{} by {}", level.name, level.author)}
+                </option>
+            }
+        })
+        .collect::<Vec<_>>();
+    view! {
+        {official}
+        {(!custom.is_empty())
+            .then(|| {
+                view! { <optgroup label="Custom levels">{custom}</optgroup> }
+            })}
+    }
 }
 
 // #[allow(clippy::pattern_type_mismatch)] // reason = "same-name variable deconstruction to references is not a type mismatch"
-fn runs_into_trs(runs: &[Run]) -> Vec<HtmlElement<Tr>> {
+fn runs_into_trs(
+    parents: &[Run],
+    runners: &Runners,
+    custom_levels: &CustomLevels,
+    runs: &[(Option<usize>, Run)],
+) -> Vec<HtmlElement<Tr>> {
     runs.iter()
-        .enumerate()
         .map(
             |(
-                idx,
-                Run {
+                rank,
+                run @ Run {
                     runner,
                     igt_ms,
                     submission_date,
@@ -162,12 +765,7 @@ fn runs_into_trs(runs: &[Run]) -> Vec<HtmlElement<Tr>> {
                 },
             )| {
                 // let  = run;
-                let igt_ms = format!(
-                    "{:02}:{:02}:{:03}",
-                    igt_ms / 1000 / 60,
-                    igt_ms / 1000 % 60,
-                    igt_ms % 1000
-                );
+                let igt_ms = format_igt(*igt_ms);
                 let submission_date = {
                     use toml::value::Date; // implements Display
                     use web_time::{Duration, SystemTime};
@@ -221,18 +819,17 @@ fn runs_into_trs(runs: &[Run]) -> Vec<HtmlElement<Tr>> {
                     }
                 };
                 let difficulty = difficulty.to_string();
-                // TODO: implement patch type
-                // let patch_release_date = patch_release_date.to_string();
+                let patch_release_date = patch_release_date.to_string();
                 // INFO: need to call `into_attribute` manually to silence `unused_import` warning
                 let _silencer = true.into_attribute();
-                let proof = proof.to_string();
-                // TODO: use gamedata::loead_runners().get(runner).unwrap_or_else(|e| ...)
-                let runner_link = format!("https://www.speedrun.com/users/{runner}");
+                let proof = proof.clone();
+                let run_link = run_link(run, parents, runners, custom_levels);
                 view! {
-                    <tr>
-                        <td>{idx + 1}</td>
+                    <tr class:obsolete=rank.is_none()>
+                        <td>{*rank}</td>
                         <td>
-                            <a href=runner_link>{runner}</a>
+                            {runner_into_view(runner, runners)}
+                            {run_link}
                         </td>
                         <td>
                             <a href=proof>{igt_ms}</a>
@@ -246,3 +843,40 @@ fn runs_into_trs(runs: &[Run]) -> Vec<HtmlElement<Tr>> {
         )
         .collect()
 }
+
+/// Link to the run's page, or to the page of the run it is a segment of, found among `parents`
+fn run_link(
+    run: &Run,
+    parents: &[Run],
+    runners: &Runners,
+    custom_levels: &CustomLevels,
+) -> Option<View> {
+    // ghpages has no router to serve the run pages
+    if cfg!(feature = "ghpages") {
+        return None;
+    }
+    if let Some(parent) = run
+        .parent
+        .and_then(|parent| parents.iter().find(|r| r.id() == parent))
+    {
+        return Some(
+            view! {
+                <a class="note" href=format!("/run/{}", parent.id())>
+                    {format!(
+                        "part of {}'s {} run", runners.name(& parent.runner), custom_levels.track_name(&
+                        parent.track)
+                    )}
+                </a>
+            }
+            .into_view(),
+        );
+    }
+    (!run.track.segments().is_empty()).then(|| {
+        view! {
+            <a class="note" href=format!("/run/{}", run.id())>
+                "splits"
+            </a>
+        }
+        .into_view()
+    })
+}
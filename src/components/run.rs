@@ -0,0 +1,147 @@
+use super::{error_into_view, fetch_run_details, format_gap, format_igt};
+use crate::gamedata::{RunDetails, RunId, Runners};
+use leptos::{
+    component, create_resource, view, CollectView, IntoView, SignalGet, SignalWith, Suspense,
+};
+use leptos_meta::Title;
+use leptos_router::use_params_map;
+
+#[cfg(debug_assertions)]
+use leptos::leptos_dom::tracing;
+
+/// Page of a single run at `/run/:id`, breaking aggregate runs down into their segments
+#[component]
+pub fn RunBreakdown() -> impl IntoView {
+    let params = use_params_map();
+    let id = move || params.with(|p| p.get("id").and_then(|id| id.parse::<RunId>().ok()));
+    let details = create_resource(id, |id| async move {
+        match id {
+            Some(id) => fetch_run_details(id).await,
+            None => Ok(None),
+        }
+    });
+    view! {
+        <Suspense fallback=|| {
+            view! {
+                <div class="leaderboard">
+                    <p class="loading">"Loading run..."</p>
+                </div>
+            }
+        }>
+            {move || {
+                details
+                    .get()
+                    .map(|details| match details {
+                        Ok(Some((details, runners))) => {
+                            view! { <Breakdown details=details runners=runners/> }.into_view()
+                        }
+                        Ok(None) => {
+                            view! {
+                                <div class="leaderboard">
+                                    <p class="error">"No run has this id"</p>
+                                </div>
+                            }
+                                .into_view()
+                        }
+                        Err(e) => view! { <div class="leaderboard">{error_into_view(&e)}</div> }.into_view(),
+                    })
+            }}
+
+        </Suspense>
+    }
+}
+
+#[allow(clippy::needless_pass_by_value)] // reason = "component props are passed by value"
+#[component]
+fn Breakdown(details: RunDetails, runners: Runners) -> impl IntoView {
+    let RunDetails {
+        run,
+        segments,
+        parent,
+        custom_levels,
+    } = details;
+    let title = format!(
+        "{}'s {} run",
+        runners.name(&run.runner),
+        custom_levels.track_name(&run.track)
+    );
+    let mut cumulative_ms = 0;
+    let rows = segments
+        .into_iter()
+        .map(|(segment, record)| {
+            cumulative_ms += u64::from(segment.igt_ms);
+            let track = custom_levels.track_name(&segment.track);
+            let track = if segment.track.segments().is_empty() {
+                track.into_view()
+            } else {
+                view! { <a href=format!("/run/{}", segment.id())>{track}</a> }.into_view()
+            };
+            view! {
+                <tr>
+                    <td>{track}</td>
+                    <td>
+                        <a href=segment.proof.clone()>{format_igt(segment.igt_ms)}</a>
+                    </td>
+                    <td>{format_igt(u32::try_from(cumulative_ms).unwrap_or(u32::MAX))}</td>
+                    <td>
+                        {record
+                            .as_ref()
+                            .map(|r| {
+                                view! {
+                                    <a href=r.proof.clone()>
+                                        {format_igt(r.igt_ms)} " by " {runners.name(&r.runner).to_owned()}
+                                    </a>
+                                }
+                            })}
+
+                    </td>
+                    <td>
+                        {record
+                            .as_ref()
+                            .map(|r| format_gap(u64::from(segment.igt_ms), u64::from(r.igt_ms)))}
+                    </td>
+                </tr>
+            }
+        })
+        .collect_view();
+    view! {
+        <Title text=format!("{title} - UKND LB")/>
+        <div class="leaderboard breakdown">
+            <div class="summary">
+                <h2>{title}</h2>
+                <p>
+                    <a href=run.proof.clone()>{format_igt(run.igt_ms)}</a>
+                    " · " {run.category.to_string()} " · " {run.difficulty.to_string()} " · "
+                    {run.patch_release_date.to_string()}
+                </p>
+                {parent
+                    .map(|p| {
+                        view! {
+                            <p>
+                                "Part of " <a href=format!("/run/{}", p.id())>
+                                    {format!(
+                                        "{}'s {} run",
+                                        runners.name(&p.runner),
+                                        custom_levels.track_name(&p.track),
+                                    )}
+                                </a>
+                            </p>
+                        }
+                    })}
+
+            </div>
+            <table>
+                <thead>
+                    <tr>
+                        <th>"Segment"</th>
+                        <th>"IGT"</th>
+                        <th>"Total"</th>
+                        <th>"Record"</th>
+                        <th>"Gap"</th>
+                    </tr>
+                </thead>
+                <tbody>{rows}</tbody>
+            </table>
+        </div>
+    }
+}
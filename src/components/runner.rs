@@ -0,0 +1,173 @@
+use super::{error_into_view, fetch_runs, format_igt};
+use crate::gamedata::{self, CustomLevels, Run, RunFilter, Runner};
+use leptos::{
+    component, create_resource, view, CollectView, IntoView, SignalGet, SignalWith, Suspense,
+};
+use leptos_meta::Title;
+use leptos_router::use_params_map;
+use std::sync::Arc;
+
+#[cfg(debug_assertions)]
+use leptos::leptos_dom::tracing;
+
+/// Page of a runner at `/runner/:id`, with their personal bests and every run they submitted
+///
+/// Any name the runner goes by works in place of the id.
+#[component]
+pub fn RunnerProfile() -> impl IntoView {
+    let params = use_params_map();
+    let name = move || params.with(|p| p.get("id").cloned().unwrap_or_default());
+    let runs = create_resource(|| (), |()| fetch_runs());
+    view! {
+        <Suspense fallback=|| {
+            view! {
+                <div class="leaderboard">
+                    <p class="loading">"Loading runner..."</p>
+                </div>
+            }
+        }>
+            {move || {
+                runs.get()
+                    .map(|runs| match runs {
+                        Ok((data, runners)) => {
+                            let name = name();
+                            runners
+                                .resolve(&name)
+                                .map_or_else(
+                                    || {
+                                        view! {
+                                            <div class="leaderboard">
+                                                <p class="error">"No runner goes by " {name.clone()}</p>
+                                            </div>
+                                        }
+                                            .into_view()
+                                    },
+                                    |runner| {
+                                        view! {
+                                            <Profile
+                                                runner=runner.clone()
+                                                runs=data.runs.clone().into()
+                                                custom_levels=data.custom_levels.clone()
+                                            />
+                                        }
+                                            .into_view()
+                                    },
+                                )
+                        }
+                        Err(e) => view! { <div class="leaderboard">{error_into_view(&e)}</div> }.into_view(),
+                    })
+            }}
+
+        </Suspense>
+    }
+}
+
+/// Position of the run on the leaderboard of its track, category and difficulty, if it is not
+/// obsolete
+fn rank(runs: &[Run], run: &Run) -> Option<usize> {
+    let filter = RunFilter {
+        track: Some(run.track.clone()),
+        category: Some(run.category),
+        difficulty: Some(run.difficulty),
+        ..RunFilter::default()
+    };
+    let id = run.id();
+    let board = runs
+        .iter()
+        .filter(|r| filter.matches(r))
+        .collect::<Vec<_>>();
+    board
+        .iter()
+        .zip(gamedata::obsolete(board.iter().copied()))
+        .filter(|&(_, obsolete)| !obsolete)
+        .position(|(r, _)| r.id() == id)
+        .map(|idx| idx + 1)
+}
+
+#[allow(clippy::needless_pass_by_value)] // reason = "component props are passed by value"
+#[component]
+fn Profile(runner: Runner, runs: Arc<[Run]>, custom_levels: CustomLevels) -> impl IntoView {
+    // runs are sorted by track and then by time, so the first run of each group is the best
+    let own_runs = runs
+        .iter()
+        .filter(|r| r.runner == runner.id)
+        .collect::<Vec<_>>();
+    let mut personal_bests = Vec::<&Run>::new();
+    for run in &own_runs {
+        if !personal_bests.iter().any(|pb| {
+            pb.track == run.track && pb.category == run.category && pb.difficulty == run.difficulty
+        }) {
+            personal_bests.push(run);
+        }
+    }
+    let joined = own_runs
+        .iter()
+        .filter_map(|r| r.submission_date.date)
+        .min()
+        .map_or_else(|| "unknown".to_owned(), |d| d.to_string());
+    let names = runner
+        .aliases
+        .iter()
+        .chain(&runner.past_names)
+        .cloned()
+        .collect::<Vec<_>>();
+    let links = [
+        ("speedrun.com", &runner.links.speedrun),
+        ("YouTube", &runner.links.youtube),
+        ("Twitch", &runner.links.twitch),
+    ]
+    .into_iter()
+    .filter_map(|(site, link)| link.clone().map(|link| view! { <a href=link>{site}</a> }))
+    .collect_view();
+    let run_row = |run: &Run| {
+        view! {
+            <tr>
+                <td>{rank(&runs, run)}</td>
+                <td>{custom_levels.track_name(&run.track)}</td>
+                <td>{run.category.to_string()}</td>
+                <td>
+                    <a href=run.proof.clone()>{format_igt(run.igt_ms)}</a>
+                </td>
+                <td>{run.submission_date.to_string()}</td>
+                <td>{run.difficulty.to_string()}</td>
+                <td>{run.patch_release_date.to_string()}</td>
+            </tr>
+        }
+    };
+    let head = || {
+        view! {
+            <thead>
+                <tr>
+                    <th>"#"</th>
+                    <th>"Track"</th>
+                    <th>"Category"</th>
+                    <th>"IGT"</th>
+                    <th>"Date"</th>
+                    <th>"Difficulty"</th>
+                    <th>"Patch"</th>
+                </tr>
+            </thead>
+        }
+    };
+    view! {
+        <Title text=format!("{} - UKND LB", runner.name)/>
+        <div class="leaderboard profile">
+            <div class="summary">
+                <h2>{runner.flag().map(|flag| format!("{flag} "))} {runner.name.clone()}</h2>
+                {(!names.is_empty()).then(|| view! { <p>"Also known as " {names.join(", ")}</p> })}
+                <p>"Joined " {joined} " · " {own_runs.len()} " runs"</p>
+                <p class="links">{links}</p>
+            </div>
+            <h3>"Personal bests"</h3>
+            <table>
+                {head()}
+                <tbody>{personal_bests.into_iter().map(run_row).collect_view()}</tbody>
+            </table>
+            <h3>"All runs"</h3>
+            <table>
+                {head()}
+                <tbody>{own_runs.into_iter().map(run_row).collect_view()}</tbody>
+            </table>
+        </div>
+    }
+}
@@ -1,8 +1,11 @@
-use serde::Deserialize;
-use std::{cmp, error::Error, fmt};
-use toml::value::Datetime;
+use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
+use std::{
+    cmp, error, fmt, num, str,
+    sync::{Arc, Mutex, PoisonError},
+};
+use toml::value::{Date, Datetime};
 
-#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Deserialize)]
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Serialize, Deserialize)]
 enum Level {
     #[serde(rename = "0-1")]
     L0_1,
@@ -83,9 +86,48 @@ enum Level {
     // #[allow(non_camel_case_types)] // reason = "Prime levels should be consistent with the rest"
     // #[serde(rename = "P-3")]
     // LP_3,
+    /// Level declared under `custom-levels`, written as its id
+    #[serde(untagged)]
     Custom(String),
 }
 
+impl Level {
+    /// Every level of the game, in order
+    const ALL: &'static [Self] = &[
+        Self::L0_1,
+        Self::L0_2,
+        Self::L0_3,
+        Self::L0_4,
+        Self::L0_5,
+        Self::L1_1,
+        Self::L1_2,
+        Self::L1_3,
+        Self::L1_4,
+        Self::L2_1,
+        Self::L2_2,
+        Self::L2_3,
+        Self::L2_4,
+        Self::L3_1,
+        Self::L3_2,
+        Self::LP_1,
+        Self::L4_1,
+        Self::L4_2,
+        Self::L4_3,
+        Self::L4_4,
+        Self::L5_1,
+        Self::L5_2,
+        Self::L5_3,
+        Self::L5_4,
+        Self::L6_1,
+        Self::L6_2,
+        Self::LP_2,
+        Self::L7_1,
+        Self::L7_2,
+        Self::L7_3,
+        Self::L7_4,
+    ];
+}
+
 impl fmt::Display for Level {
     #[inline]
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -133,9 +175,9 @@ impl fmt::Display for Level {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Deserialize)]
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Serialize, Deserialize)]
 enum Layer {
-    #[serde(alias = "Mouth Of Hell")]
+    #[serde(rename = "Mouth Of Hell", alias = "MouthOfHell")]
     MouthOfHell,
     Limbo,
     Lust,
@@ -148,6 +190,42 @@ enum Layer {
     // Treachery
 }
 
+impl Layer {
+    /// Every layer of the game, in order
+    const ALL: &'static [Self] = &[
+        Self::MouthOfHell,
+        Self::Limbo,
+        Self::Lust,
+        Self::Gluttony,
+        Self::Greed,
+        Self::Wrath,
+        Self::Heresy,
+        Self::Violence,
+    ];
+
+    /// The levels making up the layer, in order
+    ///
+    /// Prime levels are not part of any layer, they close their act instead, see [`Act::prime`]
+    pub const fn levels(&self) -> &'static [Level] {
+        match self {
+            Self::MouthOfHell => &[
+                Level::L0_1,
+                Level::L0_2,
+                Level::L0_3,
+                Level::L0_4,
+                Level::L0_5,
+            ],
+            Self::Limbo => &[Level::L1_1, Level::L1_2, Level::L1_3, Level::L1_4],
+            Self::Lust => &[Level::L2_1, Level::L2_2, Level::L2_3, Level::L2_4],
+            Self::Gluttony => &[Level::L3_1, Level::L3_2],
+            Self::Greed => &[Level::L4_1, Level::L4_2, Level::L4_3, Level::L4_4],
+            Self::Wrath => &[Level::L5_1, Level::L5_2, Level::L5_3, Level::L5_4],
+            Self::Heresy => &[Level::L6_1, Level::L6_2],
+            Self::Violence => &[Level::L7_1, Level::L7_2, Level::L7_3, Level::L7_4],
+        }
+    }
+}
+
 impl fmt::Display for Layer {
     #[inline]
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -159,23 +237,25 @@ impl fmt::Display for Layer {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Deserialize)]
+#[derive(Debug, Clone, PartialEq, PartialOrd, Eq, Ord, Serialize, Deserialize)]
 #[repr(u8)]
 enum Act {
     #[serde(
+        rename = "Act I",
+        alias = "I",
         alias = "Act1",
         alias = "Act 1",
         alias = "ActI",
-        alias = "Act I",
         alias = "InfiniteHyperdeath",
         alias = "Infinite Hyperdeath"
     )]
     I,
     #[serde(
+        rename = "Act II",
+        alias = "II",
         alias = "Act2",
         alias = "Act 2",
         alias = "ActII",
-        alias = "Act II",
         alias = "ImperfectHatred",
         alias = "Imperfect Hatred"
     )]
@@ -191,6 +271,36 @@ enum Act {
     // III,
 }
 
+impl Act {
+    /// Every act of the game, in order
+    const ALL: &'static [Self] = &[Self::I, Self::II];
+
+    /// The layers making up the act, in order
+    ///
+    /// Act I runs start from the prelude, as on speedrun.com
+    pub const fn layers(&self) -> &'static [Layer] {
+        match self {
+            Self::I => &[
+                Layer::MouthOfHell,
+                Layer::Limbo,
+                Layer::Lust,
+                Layer::Gluttony,
+            ],
+            Self::II => &[Layer::Greed, Layer::Wrath, Layer::Heresy],
+            // Self::III => &[Layer::Violence, Layer::Fraud, Layer::Treachery],
+        }
+    }
+
+    /// The prime level sitting between the act and the next one, which act runs end with
+    pub const fn prime(&self) -> Level {
+        match self {
+            Self::I => Level::LP_1,
+            Self::II => Level::LP_2,
+            // Self::III => Level::LP_3,
+        }
+    }
+}
+
 impl fmt::Display for Act {
     #[inline]
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -202,7 +312,7 @@ impl fmt::Display for Act {
     }
 }
 
-#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Track {
     Fullgame,
     #[allow(private_interfaces)] // reason = "enum must be nameable but non-constructable"
@@ -213,7 +323,76 @@ pub enum Track {
     Level(Level),
 }
 
+/// The acts making up a fullgame run, in order
+const FULLGAME_ACTS: &[Act] = &[Act::I, Act::II];
+
 impl Track {
+    /// Every track of the game, as ordered on the leaderboard
+    pub fn all() -> Vec<Self> {
+        Level::ALL
+            .iter()
+            .cloned()
+            .map(Self::Level)
+            .chain(Layer::ALL.iter().cloned().map(Self::Layer))
+            .chain(Act::ALL.iter().cloned().map(Self::Act))
+            .chain([Self::Fullgame])
+            .collect()
+    }
+
+    /// URL-safe name of the track, e.g. `6-2` or `mouth-of-hell`
+    pub fn slug(&self) -> String {
+        let slugify = |s: &str| s.to_lowercase().replace(' ', "-");
+        match self {
+            Self::Fullgame => "fullgame".to_owned(),
+            Self::Act(Act::I) => "act-1".to_owned(),
+            Self::Act(Act::II) => "act-2".to_owned(),
+            Self::Layer(l) => slugify(&l.to_string()),
+            Self::Level(Level::Custom(id)) => format!("custom-{id}"),
+            Self::Level(l) => slugify(l.to_string().split(':').next().unwrap_or_default()),
+        }
+    }
+
+    /// Inverse of [`Track::slug`], case insensitive except for the ids of custom levels, which
+    /// must be declared
+    pub fn from_slug(slug: &str, custom_levels: &CustomLevels) -> Option<Self> {
+        if let Some(id) = slug.strip_prefix("custom-") {
+            return custom_levels.get(id).map(CustomLevel::track);
+        }
+        let slug = slug.to_lowercase();
+        Self::all().into_iter().find(|t| t.slug() == slug)
+    }
+
+    /// Kind of the track, one of `level`, `layer`, `act` or `fullgame`
+    pub const fn kind(&self) -> &'static str {
+        match self {
+            Self::Level(_) => "level",
+            Self::Layer(_) => "layer",
+            Self::Act(_) => "act",
+            Self::Fullgame => "fullgame",
+        }
+    }
+
+    /// Whether the track is a custom level rather than part of the game
+    pub const fn is_custom(&self) -> bool {
+        matches!(self, Self::Level(Level::Custom(_)))
+    }
+
+    /// The tracks a run of this track is made of, in order
+    pub fn segments(&self) -> Vec<Self> {
+        match self {
+            Self::Fullgame => FULLGAME_ACTS.iter().cloned().map(Self::Act).collect(),
+            Self::Act(a) => a
+                .layers()
+                .iter()
+                .cloned()
+                .map(Self::Layer)
+                .chain([Self::Level(a.prime())])
+                .collect(),
+            Self::Layer(l) => l.levels().iter().cloned().map(Self::Level).collect(),
+            Self::Level(_) => Vec::new(),
+        }
+    }
+
     pub const fn shallow_cmp(&self, other: &Self) -> cmp::Ordering {
         use cmp::Ordering;
         match (self, other) {
@@ -268,21 +447,10 @@ impl fmt::Display for Track {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
-pub enum Category {
-    P,
-    Any,
-    NoMo,
-}
-
-impl fmt::Display for Category {
-    #[inline]
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{self:?}")
-    }
-}
+mod category;
+pub use category::{Base, Category, Modifier};
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub enum Difficulty {
     Harmless,
     Lenient,
@@ -293,6 +461,38 @@ pub enum Difficulty {
     UltrakillMustDie,
 }
 
+impl Difficulty {
+    /// Every difficulty, from the easiest to the hardest
+    pub const ALL: &'static [Self] = &[
+        Self::Harmless,
+        Self::Lenient,
+        Self::Standard,
+        Self::Violent,
+        Self::Brutal,
+        Self::UltrakillMustDie,
+    ];
+
+    /// URL-safe name of the difficulty, e.g. `violent` or `ukmd`
+    pub const fn slug(self) -> &'static str {
+        match self {
+            Self::Harmless => "harmless",
+            Self::Lenient => "lenient",
+            Self::Standard => "standard",
+            Self::Violent => "violent",
+            Self::Brutal => "brutal",
+            Self::UltrakillMustDie => "ukmd",
+        }
+    }
+
+    /// Inverse of [`Difficulty::slug`], case insensitive
+    pub fn from_slug(slug: &str) -> Option<Self> {
+        Self::ALL
+            .iter()
+            .copied()
+            .find(|d| d.slug().eq_ignore_ascii_case(slug))
+    }
+}
+
 impl fmt::Display for Difficulty {
     #[inline]
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -300,9 +500,10 @@ impl fmt::Display for Difficulty {
     }
 }
 
-type Patch = String;
+mod patch;
+pub use patch::Patch;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Run {
     pub runner: String,
     pub track: Track,
@@ -312,19 +513,552 @@ pub struct Run {
     pub difficulty: Difficulty,
     pub patch_release_date: Patch,
     pub proof: String,
+    /// The layer, act or fullgame run this run is a segment of
+    #[serde(default)]
+    pub parent: Option<RunId>,
+}
+
+impl Run {
+    pub fn id(&self) -> RunId {
+        RunId::of(self)
+    }
 }
 
+/// Stable identifier of a run, derived from the fields that tell duplicate runs apart
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct RunId(u64);
+
+impl RunId {
+    /// FNV-1a hash, so that the server and the browser agree on it
+    fn of(run: &Run) -> Self {
+        const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const PRIME: u64 = 0x0100_0000_01b3;
+        let key = format!(
+            "{}\n{}\n{}\n{}\n{:?}\n{}\n{}",
+            run.runner,
+            run.track.slug(),
+            run.category.slug(),
+            run.igt_ms,
+            run.difficulty,
+            run.patch_release_date.version(),
+            run.submission_date
+        );
+        Self(key.bytes().fold(OFFSET_BASIS, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+        }))
+    }
+}
+
+impl fmt::Display for RunId {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl str::FromStr for RunId {
+    type Err = num::ParseIntError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        u64::from_str_radix(s, 16).map(Self)
+    }
+}
+
+// as a string, since JSON numbers cannot hold every `u64`
+impl Serialize for RunId {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for RunId {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(de::Error::custom)
+    }
+}
+
+mod runners;
+pub use runners::{parse_runners, Links, NameCollision, Runner, Runners};
+
+mod custom_levels;
+pub use custom_levels::{CustomLevel, CustomLevelError, CustomLevels};
+
+mod board;
+pub use board::{BoardRuns, RunDetails};
+
 mod deserialization;
+pub use deserialization::validation::{Location, ValidationConfig, ValidationError, Violation};
 
+/// 1-based position inside the TOML document
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Position {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for Position {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}, column {}", self.line, self.column)
+    }
+}
+
+#[allow(clippy::error_impl_error)] // reason = "meant to be used as `gamedata::Error`"
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Error {
+    /// A TOML file could not be parsed
+    Syntax {
+        file: String,
+        message: String,
+        position: Option<Position>,
+    },
+    /// The TOML lacks the top level `runs` array
+    MissingRuns,
+    /// The `runs` array is empty
+    NoRuns,
+    /// The runs were parsed but break one or more rules
+    Invalid(Vec<ValidationError>),
+    /// Several runners of the registry go by the same names
+    AmbiguousRunners(Vec<NameCollision>),
+    /// Some custom levels are declared with ids that cannot identify them
+    InvalidCustomLevels(Vec<CustomLevelError>),
+    /// The server holding the runs could not be reached
+    Unavailable(String),
+}
+
+impl fmt::Display for Error {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Syntax {
+                file,
+                message,
+                position: Some(position),
+            } => write!(f, "Malformed {file} at {position}: {message}"),
+            Self::Syntax {
+                file,
+                message,
+                position: None,
+            } => write!(f, "Malformed {file}: {message}"),
+            Self::MissingRuns => write!(f, "Missing \"runs\""),
+            Self::NoRuns => write!(f, "No Runs"),
+            Self::Invalid(errors) => {
+                write!(f, "{} invalid runs", errors.len())?;
+                for e in errors {
+                    write!(f, "\n{e}")?;
+                }
+                Ok(())
+            }
+            Self::AmbiguousRunners(collisions) => {
+                write!(f, "{} names used by several runners", collisions.len())?;
+                for c in collisions {
+                    write!(f, "\n{c}")?;
+                }
+                Ok(())
+            }
+            Self::InvalidCustomLevels(errors) => {
+                write!(f, "{} invalid custom level ids", errors.len())?;
+                for e in errors {
+                    write!(f, "\n{e}")?;
+                }
+                Ok(())
+            }
+            Self::Unavailable(message) => write!(f, "Unavailable: {message}"),
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+/// Reasons runs cannot be written back to TOML
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WriteError {
+    /// An aggregate run lacks the runs of some of its segments
+    MissingSegments { runner: String, track: Track },
+    /// A segment run's aggregate run is not among the runs
+    MissingParent { runner: String, track: Track },
+    /// The file written over is malformed, or the serializer rejected the runs
+    Toml(String),
+}
+
+impl fmt::Display for WriteError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::MissingSegments { runner, track } => {
+                write!(f, "{runner}'s {track} run lacks the runs of its segments")
+            }
+            Self::MissingParent { runner, track } => {
+                write!(f, "{runner}'s {track} run is a segment of a missing run")
+            }
+            Self::Toml(message) => write!(f, "Unrepresentable TOML: {message}"),
+        }
+    }
+}
+
+impl error::Error for WriteError {}
+
+#[cfg(not(feature = "hydrate"))]
+mod loader;
+#[cfg(not(feature = "hydrate"))]
+pub use loader::{load_run_data, load_runners_data};
+
+/// How the difficulty of a [`RunFilter`] matches runs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DifficultyMode {
+    /// Only runs on that difficulty
+    #[default]
+    Exact,
+    /// Runs on that difficulty or on a harder one
+    Minimum,
+}
+
+/// Criteria a run has to meet, unset ones are met by every run
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunFilter {
+    pub track: Option<Track>,
+    pub category: Option<Category>,
+    pub difficulty: Option<Difficulty>,
+    #[serde(default)]
+    pub difficulty_mode: DifficultyMode,
+    /// Earliest patch, included
+    pub patch_from: Option<Patch>,
+    /// Latest patch, included
+    pub patch_until: Option<Patch>,
+    /// Earliest submission date, included
+    pub submitted_from: Option<Date>,
+    /// Latest submission date, included
+    pub submitted_until: Option<Date>,
+    /// Aggregate run the runs are segments of
+    pub parent: Option<RunId>,
+}
+
+impl RunFilter {
+    pub fn matches(&self, run: &Run) -> bool {
+        self.track.as_ref().is_none_or(|t| *t == run.track)
+            && self.category.is_none_or(|c| run.category.satisfies(c))
+            && self.difficulty.is_none_or(|d| match self.difficulty_mode {
+                DifficultyMode::Exact => d == run.difficulty,
+                DifficultyMode::Minimum => d <= run.difficulty,
+            })
+            && self
+                .patch_from
+                .as_ref()
+                .is_none_or(|p| *p <= run.patch_release_date)
+            && self
+                .patch_until
+                .as_ref()
+                .is_none_or(|p| *p >= run.patch_release_date)
+            && self
+                .submitted_from
+                .is_none_or(|d| run.submission_date.date.is_some_and(|date| d <= date))
+            && self
+                .submitted_until
+                .is_none_or(|d| run.submission_date.date.is_some_and(|date| d >= date))
+            && self.parent.is_none_or(|p| run.parent == Some(p))
+    }
+}
+
+/// Flags the runs beaten by an earlier run of the same runner, so that a board only ranks the
+/// personal best of each runner, whichever difficulty it was set on
+///
+/// The runs must belong to a single board and be sorted by time, as [`parse_runs`] sorts them.
+pub fn obsolete<'a, I: IntoIterator<Item = &'a Run>>(runs: I) -> Vec<bool> {
+    let mut seen = Vec::<&str>::new();
+    runs.into_iter()
+        .map(|run| {
+            let obsolete = seen.contains(&run.runner.as_str());
+            if !obsolete {
+                seen.push(&run.runner);
+            }
+            obsolete
+        })
+        .collect()
+}
+
+/// Sum of the best times on the levels making up the filter's track, the time a run matching every
+/// level record would get
+///
+/// Returns `None` if the filter has no track or if one of the levels has no run meeting the filter
+pub fn sum_of_best(runs: &[Run], filter: &RunFilter) -> Option<u64> {
+    match filter.track.as_ref()? {
+        Track::Level(_) => runs
+            .iter()
+            .filter(|r| filter.matches(r))
+            .map(|r| u64::from(r.igt_ms))
+            .min(),
+        track @ (Track::Layer(_) | Track::Act(_) | Track::Fullgame) => track
+            .segments()
+            .into_iter()
+            .map(|segment| {
+                sum_of_best(
+                    runs,
+                    &RunFilter {
+                        track: Some(segment),
+                        ..filter.clone()
+                    },
+                )
+            })
+            .sum(),
+    }
+}
+
+/// Orders runs by track and then by time, with the more impressive runs first on ties
+fn sort_runs(runs: &mut [Run]) {
+    runs.sort_by(|a, b| {
+        a.track
+            .cmp(&b.track)
+            .then(a.igt_ms.cmp(&b.igt_ms))
+            .then(b.difficulty.cmp(&a.difficulty))
+            .then(b.patch_release_date.cmp(&a.patch_release_date))
+            .then(a.submission_date.cmp(&b.submission_date))
+            .then(a.runner.cmp(&b.runner))
+            .then_with(|| panic!("Duplicate runs {a:?} {b:?}"))
+    });
+}
+
+/// Runs of the run data, along with the custom levels and the validation settings declared next to
+/// them
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunData {
+    pub runs: Vec<Run>,
+    pub custom_levels: CustomLevels,
+    pub validation: ValidationConfig,
+}
+
+/// Parses and validates the runs, keeping them in file order
+///
+/// Runner names get resolved to the ids of the runners in the registry.
+///
 /// # Errors
-/// Errors on parsing errors or on an empty set of runs
-// TODO: move away from Box<dyn Error> once the deserialization::parse_toml function does
-pub fn load_runs() -> Result<Vec<Run>, Box<dyn Error>> {
-    // I have no idea how to read files at runtime
-    // HACK: embed the file in the binary
-    match deserialization::parse_toml(std::include_str!("../assets/run_data.toml")) {
-        Ok(v) if v.is_empty() => Err("No Runs".into()),
-        v => v,
-    }
-    // TODO: add caching
+/// Errors on parsing errors or on invalid runs
+pub fn parse_runs_unsorted(toml_data: &str, runners_data: &str) -> Result<RunData, Error> {
+    deserialization::parse_toml(toml_data, &parse_runners(runners_data)?)
+}
+
+/// Writes the run data back over the file `toml_data` in the nested TOML layout, nesting segments
+/// under their parent run
+///
+/// The `[validation]` table, the custom levels and the runs replace the ones of the file, whose
+/// comments are kept in place. Top level runs keep their order and segments follow the order of
+/// their track. Values are written in a canonical form: runners as their registry ids, patches as
+/// their version labels and in-game times split into groups of 3 digits, so rewriting a written
+/// file leaves it unchanged.
+///
+/// # Errors
+/// Errors if an aggregate run lacks some segments, if a segment lacks its aggregate run or if
+/// `toml_data` is malformed
+pub fn write_runs(toml_data: &str, data: &RunData) -> Result<String, WriteError> {
+    deserialization::write_toml(toml_data, data)
+}
+
+/// Parses, validates and sorts the runs, reusing the previous result if the data did not change
+///
+/// Runner names get resolved to the ids of the runners in the registry.
+///
+/// # Errors
+/// Errors on parsing errors, on invalid runs or on an empty set of runs
+pub fn parse_runs(toml_data: &str, runners_data: &str) -> Result<Arc<RunData>, Error> {
+    type Cache = Option<((String, String), Result<Arc<RunData>, Error>)>;
+    static CACHE: Mutex<Cache> = Mutex::new(None);
+
+    let mut cache = CACHE.lock().unwrap_or_else(PoisonError::into_inner);
+    if let Some(((data, runners), run_data)) = cache.as_ref() {
+        if data == toml_data && runners == runners_data {
+            return run_data.clone();
+        }
+    }
+    let run_data = match parse_runs_unsorted(toml_data, runners_data) {
+        Ok(d) if d.runs.is_empty() => Err(Error::NoRuns),
+        Ok(mut d) => {
+            sort_runs(&mut d.runs);
+            Ok(Arc::new(d))
+        }
+        Err(e) => Err(e),
+    };
+    *cache = Some((
+        (toml_data.to_owned(), runners_data.to_owned()),
+        run_data.clone(),
+    ));
+    run_data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        obsolete, parse_runs_unsorted, write_runs, Category, Difficulty, Error, Position, Run,
+    };
+
+    const RUN_DATA: &str = std::include_str!("../assets/run_data.toml");
+    const RUNNERS_DATA: &str = std::include_str!("../assets/runners.toml");
+
+    #[test]
+    fn written_runs_parse_back_unchanged() {
+        let data =
+            parse_runs_unsorted(RUN_DATA, RUNNERS_DATA).expect("the sample runs should parse");
+        let written = write_runs(RUN_DATA, &data).expect("the sample runs should be writable");
+        let reparsed =
+            parse_runs_unsorted(&written, RUNNERS_DATA).expect("the written runs should parse");
+        assert_eq!(reparsed, data);
+        assert_eq!(
+            write_runs(&written, &reparsed).expect("the written runs should be writable"),
+            written
+        );
+    }
+
+    #[test]
+    fn written_runs_keep_comments_and_settings() {
+        let data =
+            parse_runs_unsorted(RUN_DATA, RUNNERS_DATA).expect("the sample runs should parse");
+        let written = write_runs(RUN_DATA, &data).expect("the sample runs should be writable");
+        assert!(written.starts_with("[validation]\nigt_sum_tolerance_ms = 0               # "));
+        assert!(written.contains("\n# Custom levels runs may be submitted on"));
+        assert!(written.contains(
+            "category = \"P\"                         # Any | P | NoMo, or several modifiers"
+        ));
+    }
+
+    #[test]
+    fn written_custom_levels_parse_back() {
+        let run_data = r#"
+[[custom-levels]]
+id = "some-level"
+name = "Some Level"
+author = "Someone"
+link = "https://example.com"
+version = "1.0"
+
+[[runs]]
+runner = "TheSast"
+track = "some-level"
+igt_ms = 61_000
+category = "Any"
+submission_date = 2023-01-01
+difficulty = "Violent"
+patch_release_date = "11a"
+proof = "https://youtu.be/proof"
+"#;
+        let data =
+            parse_runs_unsorted(run_data, RUNNERS_DATA).expect("the custom run should parse");
+        let written = write_runs("", &data).expect("the custom run should be writable");
+        assert_eq!(
+            parse_runs_unsorted(&written, RUNNERS_DATA).expect("the written run should parse"),
+            data
+        );
+    }
+
+    #[test]
+    fn written_times_keep_digit_separators() {
+        let data =
+            parse_runs_unsorted(RUN_DATA, RUNNERS_DATA).expect("the sample runs should parse");
+        let written = write_runs("", &data).expect("the sample runs should be writable");
+        assert!(written.contains("igt_ms = 55_140\n"));
+        assert!(written.contains("igt_ms = 298_566\n"));
+    }
+
+    #[test]
+    fn broken_segment_is_not_taken_for_a_custom_level() {
+        let run = r#"
+[[runs]]
+runner = "Someone"
+track = "Gluttony"
+igt_ms = 60_000
+category = "Any"
+submission_date = 2023-01-01
+difficulty = "Violent"
+patch_release_date = "EA"
+proof = "https://youtu.be/proof"
+
+[[runs.levels]]
+runner = "Someone"
+track = "3-1"
+igt_ms = 30_000
+category = "Any"
+submission_date = 2023-01-01
+difficulty = "Violent"
+patch_release_date = "EA"
+proof = "https://youtu.be/proof"
+
+[[runs.levels]]
+runner = "Someone"
+track = "3-2"
+igt_ms = 30_000
+category = "Any"
+submission_date = 2023-01-01
+difficulty = "Violent"
+patch_release_date = "EA"
+"#;
+        assert!(matches!(
+            parse_runs_unsorted(run, RUNNERS_DATA),
+            Err(Error::Syntax { .. })
+        ));
+    }
+
+    #[test]
+    fn syntax_errors_point_at_the_broken_run() {
+        let runs = r#"
+[[runs]]
+runner = "TheSast"
+track = "6-2"
+igt_ms = 28_929
+category = "P"
+submission_date = 2022-09-01
+difficulty = "Violent"
+patch_release_date = "11a"
+proof = "https://youtu.be/proof"
+
+[[runs]]
+runner = "TheSast"
+track = "6-1"
+igt_ms = 28_929
+category = "P"
+submission_date = 2022-09-01
+difficulty = "Violent"
+patch_release_date = "11a"
+"#;
+        assert_eq!(
+            parse_runs_unsorted(runs, RUNNERS_DATA),
+            Err(Error::Syntax {
+                file: "run_data.toml".to_owned(),
+                message: "missing field `proof`".to_owned(),
+                position: Some(Position {
+                    line: 12,
+                    column: 1
+                }),
+            })
+        );
+    }
+
+    #[test]
+    fn obsolete_keeps_one_run_per_runner_across_difficulties() {
+        let data =
+            parse_runs_unsorted(RUN_DATA, RUNNERS_DATA).expect("the sample runs should parse");
+        let violent = data.runs[0].clone();
+        let brutal = Run {
+            igt_ms: violent.igt_ms + 1,
+            difficulty: Difficulty::Brutal,
+            ..violent.clone()
+        };
+        let other = Run {
+            runner: "someone-else".to_owned(),
+            igt_ms: violent.igt_ms + 2,
+            ..violent.clone()
+        };
+        assert_eq!(obsolete([&violent, &brutal, &other]), [false, true, false]);
+    }
+
+    #[test]
+    fn runs_in_other_categories_have_other_ids() {
+        let data =
+            parse_runs_unsorted(RUN_DATA, RUNNERS_DATA).expect("the sample runs should parse");
+        let run = data.runs[0].clone();
+        let other = Run {
+            category: Category::default(),
+            ..run.clone()
+        };
+        assert_ne!(run.category, other.category);
+        assert_ne!(run.id(), other.id());
+    }
 }
@@ -0,0 +1,149 @@
+//! What the pages show of the runs, so that they only fetch that much
+use super::{
+    sum_of_best, CustomLevels, Deserialize, Run, RunData, RunFilter, RunId, Serialize, Track,
+};
+
+/// The runs a board shows for its filter
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BoardRuns {
+    /// The runs meeting the filter, in leaderboard order
+    pub runs: Vec<Run>,
+    /// The runs the shown runs are segments of
+    pub parents: Vec<Run>,
+    /// See [`sum_of_best`]
+    pub sum_of_best: Option<u64>,
+}
+
+/// A run along with the runs its page refers to
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RunDetails {
+    pub run: Run,
+    /// The segments of the run in order, each along with the record of its track
+    pub segments: Vec<(Run, Option<Run>)>,
+    pub parent: Option<Run>,
+    /// See [`RunData::custom_levels`], to name the tracks
+    pub custom_levels: CustomLevels,
+}
+
+impl RunData {
+    fn get(&self, id: RunId) -> Option<&Run> {
+        self.runs.iter().find(|r| r.id() == id)
+    }
+
+    /// The tracks with runs, in leaderboard order
+    pub fn tracks(&self) -> Vec<Track> {
+        let mut tracks = self
+            .runs
+            .iter()
+            .map(|r| r.track.clone())
+            .collect::<Vec<_>>();
+        tracks.dedup();
+        tracks
+    }
+
+    /// The runs a board with the filter shows
+    pub fn board(&self, filter: &RunFilter) -> BoardRuns {
+        let runs = self
+            .runs
+            .iter()
+            .filter(|r| filter.matches(r))
+            .cloned()
+            .collect::<Vec<_>>();
+        let mut parents = Vec::<Run>::new();
+        for parent in runs.iter().filter_map(|r| self.get(r.parent?)) {
+            if !parents.contains(parent) {
+                parents.push(parent.clone());
+            }
+        }
+        BoardRuns {
+            runs,
+            parents,
+            sum_of_best: sum_of_best(&self.runs, filter),
+        }
+    }
+
+    /// The run with the id along with the runs its page refers to, if any run has that id
+    pub fn details(&self, id: RunId) -> Option<RunDetails> {
+        let run = self.get(id)?.clone();
+        let order = run.track.segments();
+        let mut segment_runs = self
+            .runs
+            .iter()
+            .filter(|r| r.parent == Some(id))
+            .collect::<Vec<_>>();
+        segment_runs.sort_by_key(|s| order.iter().position(|t| *t == s.track));
+        let segments = segment_runs
+            .into_iter()
+            .map(|segment| {
+                let filter = RunFilter {
+                    track: Some(segment.track.clone()),
+                    category: Some(segment.category),
+                    difficulty: Some(segment.difficulty),
+                    ..RunFilter::default()
+                };
+                // runs are sorted by time, so the first matching run holds the record
+                let record = self.runs.iter().find(|r| filter.matches(r)).cloned();
+                (segment.clone(), record)
+            })
+            .collect();
+        Some(RunDetails {
+            parent: run.parent.and_then(|parent| self.get(parent)).cloned(),
+            run,
+            segments,
+            custom_levels: self.custom_levels.clone(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{parse_runs, Category, Difficulty, RunFilter, Track};
+
+    const RUN_DATA: &str = std::include_str!("../../assets/run_data.toml");
+    const RUNNERS_DATA: &str = std::include_str!("../../assets/runners.toml");
+
+    #[test]
+    fn boards_bring_the_parents_of_their_segments() {
+        let data = parse_runs(RUN_DATA, RUNNERS_DATA).expect("the sample runs should parse");
+        let board = data.board(&RunFilter {
+            track: Track::from_slug("0-1", &data.custom_levels),
+            category: Category::from_slug("p"),
+            difficulty: Some(Difficulty::Violent),
+            ..RunFilter::default()
+        });
+        assert_eq!(board.runs.len(), 2);
+        assert_eq!(board.sum_of_best, Some(36_414));
+        assert!(board
+            .runs
+            .iter()
+            .all(|r| board.parents.iter().any(|p| Some(p.id()) == r.parent)));
+    }
+
+    #[test]
+    fn details_order_the_segments_along_with_their_records() {
+        let data = parse_runs(RUN_DATA, RUNNERS_DATA).expect("the sample runs should parse");
+        let run = data
+            .runs
+            .iter()
+            .find(|r| !r.track.segments().is_empty())
+            .expect("the sample runs should hold an aggregate run");
+        let details = data.details(run.id()).expect("the run should have details");
+        assert_eq!(
+            details
+                .segments
+                .iter()
+                .map(|(segment, _)| segment.track.clone())
+                .collect::<Vec<_>>(),
+            run.track.segments()
+        );
+        assert!(details
+            .segments
+            .iter()
+            .all(|(segment, record)| record.as_ref().is_some_and(|r| r.igt_ms <= segment.igt_ms)));
+        let segment = &details.segments[0].0;
+        assert_eq!(
+            data.details(segment.id()).and_then(|d| d.parent),
+            Some(run.clone())
+        );
+    }
+}
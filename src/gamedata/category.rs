@@ -0,0 +1,291 @@
+use super::{Deserialize, Deserializer, Serialize, Serializer};
+use serde::{de, ser::SerializeSeq};
+use std::{fmt, str};
+
+/// Rules every run of a category follows before any modifier
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Base {
+    /// Finishing the track by any means
+    #[default]
+    Any,
+}
+
+impl Base {
+    const fn name(self) -> &'static str {
+        match self {
+            Self::Any => "Any",
+        }
+    }
+}
+
+/// Restriction added on top of the base rules
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Modifier {
+    /// Getting the P rank
+    P,
+    /// Not moving
+    NoMo,
+}
+
+impl Modifier {
+    pub const ALL: &'static [Self] = &[Self::P, Self::NoMo];
+
+    /// Name written in the run data
+    pub const fn name(self) -> &'static str {
+        match self {
+            Self::P => "P",
+            Self::NoMo => "NoMo",
+        }
+    }
+
+    /// URL-safe name of the modifier
+    pub const fn slug(self) -> &'static str {
+        match self {
+            Self::P => "p",
+            Self::NoMo => "nomo",
+        }
+    }
+
+    const fn bit(self) -> u8 {
+        match self {
+            Self::P => 1,
+            Self::NoMo => 2,
+        }
+    }
+}
+
+/// Rules a run was played under, a base rule set along with any number of modifiers
+///
+/// Written in the run data as `"Any"`, as a single modifier such as `"P"`, or as an array of
+/// modifiers such as `["P", "NoMo"]`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Category {
+    pub base: Base,
+    /// Bit set of the modifiers
+    modifiers: u8,
+}
+
+impl Category {
+    pub const fn has(self, modifier: Modifier) -> bool {
+        self.modifiers & modifier.bit() != 0
+    }
+
+    /// The category with the modifier added
+    #[must_use]
+    pub const fn with(self, modifier: Modifier) -> Self {
+        Self {
+            modifiers: self.modifiers | modifier.bit(),
+            ..self
+        }
+    }
+
+    /// The category with the modifier added if it was missing or removed otherwise
+    #[must_use]
+    pub const fn toggled(self, modifier: Modifier) -> Self {
+        Self {
+            modifiers: self.modifiers ^ modifier.bit(),
+            ..self
+        }
+    }
+
+    /// The modifiers of the category, in declaration order
+    pub fn modifiers(self) -> impl Iterator<Item = Modifier> {
+        Modifier::ALL.iter().copied().filter(move |m| self.has(*m))
+    }
+
+    /// URL-safe name of the category, e.g. `any`, `p` or `p-nomo`
+    pub fn slug(self) -> String {
+        if self.modifiers == 0 {
+            return self.base.name().to_lowercase();
+        }
+        self.modifiers()
+            .map(Modifier::slug)
+            .collect::<Vec<_>>()
+            .join("-")
+    }
+
+    /// Inverse of [`Category::slug`], case insensitive
+    ///
+    /// Only canonical slugs are accepted, so that a board has a single URL: `nomo-p` or `p-p` are
+    /// rejected in favor of `p-nomo` and `p`.
+    pub fn from_slug(slug: &str) -> Option<Self> {
+        let slug = slug.to_lowercase();
+        if slug == Base::Any.name().to_lowercase() {
+            return Some(Self::default());
+        }
+        slug.split('-')
+            .try_fold(Self::default(), |category, part| {
+                Modifier::ALL
+                    .iter()
+                    .find(|m| m.slug() == part)
+                    .map(|m| category.with(*m))
+            })
+            .filter(|category| category.slug() == slug)
+    }
+
+    /// Whether a run in this category also meets the rules of `other`, so that it counts on the
+    /// boards of both
+    ///
+    /// A run satisfies the categories with the same base and a subset of its modifiers, so every
+    /// category satisfies Any%.
+    pub fn satisfies(self, other: Self) -> bool {
+        self.base == other.base && self.modifiers & other.modifiers == other.modifiers
+    }
+
+    /// The category with the modifiers of both
+    const fn union(self, other: Self) -> Self {
+        Self {
+            modifiers: self.modifiers | other.modifiers,
+            ..self
+        }
+    }
+}
+
+impl From<Modifier> for Category {
+    fn from(modifier: Modifier) -> Self {
+        Self::default().with(modifier)
+    }
+}
+
+impl fmt::Display for Category {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.modifiers == 0 {
+            return write!(f, "{}", self.base.name());
+        }
+        let names = self.modifiers().map(Modifier::name).collect::<Vec<_>>();
+        write!(f, "{}", names.join("+"))
+    }
+}
+
+impl str::FromStr for Category {
+    type Err = String;
+
+    /// Parses `Any`, a modifier or several modifiers joined by `+`, e.g. `P+NoMo`
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        s.split('+')
+            .map(str::trim)
+            .try_fold(Self::default(), |category, name| {
+                if name == Base::Any.name() {
+                    return Ok(category);
+                }
+                Modifier::ALL
+                    .iter()
+                    .find(|m| m.name() == name)
+                    .map(|m| category.with(*m))
+                    .ok_or_else(|| format!("unknown category \"{name}\", expected Any, P or NoMo"))
+            })
+    }
+}
+
+impl Serialize for Category {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let modifiers = self.modifiers().collect::<Vec<_>>();
+        match modifiers.as_slice() {
+            [] => serializer.serialize_str(self.base.name()),
+            [modifier] => serializer.serialize_str(modifier.name()),
+            _ => {
+                let mut seq = serializer.serialize_seq(Some(modifiers.len()))?;
+                for modifier in modifiers {
+                    seq.serialize_element(modifier.name())?;
+                }
+                seq.end()
+            }
+        }
+    }
+}
+
+struct CategoryVisitor;
+
+impl<'de> de::Visitor<'de> for CategoryVisitor {
+    type Value = Category;
+
+    fn expecting(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "a category such as \"P\", or an array of them such as [\"P\", \"NoMo\"]"
+        )
+    }
+
+    fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+        v.parse().map_err(E::custom)
+    }
+
+    fn visit_seq<A: de::SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut category = Category::default();
+        while let Some(name) = seq.next_element::<String>()? {
+            category = category.union(name.parse().map_err(de::Error::custom)?);
+        }
+        Ok(category)
+    }
+}
+
+impl<'de> Deserialize<'de> for Category {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(CategoryVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Base, Category, Modifier};
+
+    const P_NOMO: Category = Category {
+        base: Base::Any,
+        modifiers: 0,
+    }
+    .with(Modifier::P)
+    .with(Modifier::NoMo);
+
+    #[derive(Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+    struct Entry {
+        category: Category,
+    }
+
+    #[test]
+    fn slugs_round_trip() {
+        for category in [Category::default(), Modifier::P.into(), P_NOMO] {
+            assert_eq!(Category::from_slug(&category.slug()), Some(category));
+        }
+        assert_eq!(Category::from_slug("P-NoMo"), Some(P_NOMO));
+    }
+
+    #[test]
+    fn rejects_non_canonical_slugs() {
+        for slug in ["p-p", "nomo-p", "any-p", "p-", ""] {
+            assert_eq!(Category::from_slug(slug), None, "{slug}");
+        }
+    }
+
+    #[test]
+    fn parses_names() {
+        assert_eq!("Any".parse(), Ok(Category::default()));
+        assert_eq!("P".parse(), Ok(Category::from(Modifier::P)));
+        assert_eq!("NoMo + P".parse(), Ok(P_NOMO));
+        assert!("Q".parse::<Category>().is_err());
+        assert_eq!(P_NOMO.to_string().parse(), Ok(P_NOMO));
+    }
+
+    #[test]
+    fn deserializes_strings_and_arrays() {
+        let parse = |toml_data: &str| toml::from_str::<Entry>(toml_data).map(|e| e.category);
+        assert_eq!(parse(r#"category = "Any""#), Ok(Category::default()));
+        assert_eq!(parse(r#"category = "NoMo""#), Ok(Modifier::NoMo.into()));
+        assert_eq!(parse(r#"category = ["P", "NoMo"]"#), Ok(P_NOMO));
+        assert_eq!(parse("category = []"), Ok(Category::default()));
+        assert!(parse(r#"category = "Q""#).is_err());
+        assert!(parse(r#"category = ["P", "Q"]"#).is_err());
+    }
+
+    #[test]
+    fn serializes_round_trip() {
+        for category in [Category::default(), Modifier::P.into(), P_NOMO] {
+            let written =
+                toml::to_string(&Entry { category }).expect("categories should serialize");
+            assert_eq!(toml::from_str(&written), Ok(Entry { category }));
+        }
+        let written =
+            toml::to_string(&Entry { category: P_NOMO }).expect("categories should serialize");
+        assert_eq!(written, "category = [\"P\", \"NoMo\"]\n");
+    }
+}
@@ -0,0 +1,146 @@
+//! Custom levels declared by the run data, the only ones runs may be submitted on
+use super::{Deserialize, Level, Serialize, Track};
+use std::{collections::BTreeSet, fmt};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct CustomLevel {
+    /// Stable identifier, written as the track of the runs
+    pub id: String,
+    /// Name shown on the leaderboard
+    pub name: String,
+    pub author: String,
+    /// Workshop page or download link
+    pub link: String,
+    /// Version of the level the runs are played on
+    pub version: String,
+}
+
+impl CustomLevel {
+    /// The track of the runs on the level
+    pub fn track(&self) -> Track {
+        Track::Level(Level::Custom(self.id.clone()))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CustomLevels(Vec<CustomLevel>);
+
+impl CustomLevels {
+    pub fn get(&self, id: &str) -> Option<&CustomLevel> {
+        self.0.iter().find(|l| l.id == id)
+    }
+
+    /// Name the track is shown under, the declared name for custom levels
+    pub fn track_name(&self, track: &Track) -> String {
+        if let Track::Level(Level::Custom(id)) = track {
+            if let Some(level) = self.get(id) {
+                return level.name.clone();
+            }
+        }
+        track.to_string()
+    }
+
+    pub const fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// The declared levels, in declaration order
+    pub fn iter(&self) -> impl Iterator<Item = &CustomLevel> {
+        self.0.iter()
+    }
+
+    /// The ids that cannot be told apart from other ones or used in URLs, in declaration order
+    pub(super) fn errors(&self) -> Vec<CustomLevelError> {
+        let mut seen = BTreeSet::new();
+        let mut duplicates = BTreeSet::new();
+        let mut errors = Vec::new();
+        for level in &self.0 {
+            let id = &level.id;
+            if id.is_empty()
+                || !id
+                    .bytes()
+                    .all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'-')
+            {
+                errors.push(CustomLevelError::UnsafeId(id.clone()));
+            } else if !seen.insert(id) && duplicates.insert(id) {
+                errors.push(CustomLevelError::DuplicateId(id.clone()));
+            }
+        }
+        errors
+    }
+}
+
+/// Declared custom level whose id cannot identify it in the runs and URLs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CustomLevelError {
+    /// Several levels are declared with the id
+    DuplicateId(String),
+    /// The id holds other characters than lowercase ASCII letters, digits and dashes
+    UnsafeId(String),
+}
+
+impl fmt::Display for CustomLevelError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::DuplicateId(id) => write!(f, "\"{id}\" is the id of several custom levels"),
+            Self::UnsafeId(id) => write!(
+                f,
+                "\"{id}\" should only hold lowercase letters, digits and dashes"
+            ),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CustomLevel, CustomLevelError, CustomLevels, Level, Track};
+
+    fn levels(ids: &[&str]) -> CustomLevels {
+        CustomLevels(
+            ids.iter()
+                .map(|id| CustomLevel {
+                    id: (*id).to_owned(),
+                    name: "Some Level".to_owned(),
+                    author: "Someone".to_owned(),
+                    link: "https://example.com".to_owned(),
+                    version: "1.0".to_owned(),
+                })
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn accepts_url_safe_ids() {
+        assert_eq!(levels(&["heart-of-the-sunrise", "7-s"]).errors(), []);
+    }
+
+    #[test]
+    fn rejects_duplicate_ids_once() {
+        assert_eq!(
+            levels(&["level", "other", "level", "level"]).errors(),
+            [CustomLevelError::DuplicateId("level".to_owned())]
+        );
+    }
+
+    #[test]
+    fn rejects_unsafe_ids() {
+        assert_eq!(
+            levels(&["", "Level", "some level", "lévy"]).errors(),
+            ["", "Level", "some level", "lévy"].map(|id| CustomLevelError::UnsafeId(id.to_owned()))
+        );
+    }
+
+    #[test]
+    fn names_custom_levels_by_their_declared_name() {
+        let levels = levels(&["heart-of-the-sunrise"]);
+        let track = |id: &str| Track::Level(Level::Custom(id.to_owned()));
+        assert_eq!(
+            levels.track_name(&track("heart-of-the-sunrise")),
+            "Some Level"
+        );
+        assert_eq!(levels.track_name(&track("undeclared")), "undeclared");
+        assert_eq!(levels.track_name(&Track::Fullgame), "Fullgame");
+    }
+}
@@ -1,7 +1,17 @@
-use super::{Act, Category, Datetime, Deserialize, Difficulty, Layer, Level, Patch, Run, Track};
-use std::error::Error;
+use super::{
+    Act, Category, CustomLevels, Datetime, Deserialize, Difficulty, Error, Layer, Level, Patch,
+    Position, Run, RunData, RunId, Runners, Serialize, Track, WriteError,
+};
+use std::{collections::BTreeMap, ops::Range};
+use toml::{de, Spanned};
+use toml_edit::{DocumentMut, Item, Table, Value};
 
-#[derive(Deserialize)]
+pub(super) mod validation;
+use validation::ValidationConfig;
+
+// so that an aggregate run misspelling its segments key is not taken for a custom level run
+#[derive(Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
 struct IndividualLevelRun {
     runner: String,
     track: Level,
@@ -24,6 +34,7 @@ impl From<IndividualLevelRun> for Run {
             difficulty: item.difficulty,
             patch_release_date: item.patch_release_date,
             proof: item.proof,
+            parent: None,
         }
     }
 }
@@ -34,7 +45,7 @@ impl From<IndividualLevelRun> for Vec<Run> {
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
 struct LayerRun {
     runner: String,
     track: Layer,
@@ -49,24 +60,31 @@ struct LayerRun {
 
 impl From<LayerRun> for Vec<Run> {
     fn from(item: LayerRun) -> Self {
+        let parent = Run {
+            runner: item.runner,
+            track: Track::Layer(item.track),
+            igt_ms: item.igt_ms,
+            category: item.category,
+            submission_date: item.submission_date,
+            difficulty: item.difficulty,
+            patch_release_date: item.patch_release_date,
+            proof: item.proof,
+            parent: None,
+        };
+        let id = parent.id();
         item.levels
             .into_iter()
             .flat_map(Into::<Self>::into)
-            .chain([Run {
-                runner: item.runner,
-                track: Track::Layer(item.track),
-                igt_ms: item.igt_ms,
-                category: item.category,
-                submission_date: item.submission_date,
-                difficulty: item.difficulty,
-                patch_release_date: item.patch_release_date,
-                proof: item.proof,
-            }])
+            .map(|run| Run {
+                parent: run.parent.or(Some(id)),
+                ..run
+            })
+            .chain([parent])
             .collect()
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
 struct ActRun {
     runner: String,
     track: Act,
@@ -77,28 +95,39 @@ struct ActRun {
     patch_release_date: Patch,
     proof: String,
     layers: Vec<LayerRun>,
+    /// Run of the prime level closing the act, see [`Act::prime`]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    prime: Option<IndividualLevelRun>,
 }
 
 impl From<ActRun> for Vec<Run> {
     fn from(item: ActRun) -> Self {
+        let parent = Run {
+            runner: item.runner,
+            track: Track::Act(item.track),
+            igt_ms: item.igt_ms,
+            category: item.category,
+            submission_date: item.submission_date,
+            difficulty: item.difficulty,
+            patch_release_date: item.patch_release_date,
+            proof: item.proof,
+            parent: None,
+        };
+        let id = parent.id();
         item.layers
             .into_iter()
             .flat_map(Into::<Self>::into)
-            .chain([Run {
-                runner: item.runner,
-                track: Track::Act(item.track),
-                igt_ms: item.igt_ms,
-                category: item.category,
-                submission_date: item.submission_date,
-                difficulty: item.difficulty,
-                patch_release_date: item.patch_release_date,
-                proof: item.proof,
-            }])
+            .chain(item.prime.into_iter().flat_map(Into::<Self>::into))
+            .map(|run| Run {
+                parent: run.parent.or(Some(id)),
+                ..run
+            })
+            .chain([parent])
             .collect()
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Serialize, Deserialize)]
 struct FullgameRun {
     runner: String,
     igt_ms: u32,
@@ -112,25 +141,32 @@ struct FullgameRun {
 
 impl From<FullgameRun> for Vec<Run> {
     fn from(item: FullgameRun) -> Self {
+        let parent = Run {
+            runner: item.runner,
+            track: Track::Fullgame,
+            igt_ms: item.igt_ms,
+            category: item.category,
+            submission_date: item.submission_date,
+            difficulty: item.difficulty,
+            patch_release_date: item.patch_release_date,
+            proof: item.proof,
+            parent: None,
+        };
+        let id = parent.id();
         item.acts
             .into_iter()
             .flat_map(Into::<Self>::into)
-            .chain([Run {
-                runner: item.runner,
-                track: Track::Fullgame,
-                igt_ms: item.igt_ms,
-                category: item.category,
-                submission_date: item.submission_date,
-                difficulty: item.difficulty,
-                patch_release_date: item.patch_release_date,
-                proof: item.proof,
-            }])
+            .map(|run| Run {
+                parent: run.parent.or(Some(id)),
+                ..run
+            })
+            .chain([parent])
             .collect()
     }
 }
 
-// it will kind of `match` against possible variants, so the easiest to match should go last
-#[derive(Deserialize)]
+// untagged so that every kind of run is written as a plain table, see `from_value` for reading
+#[derive(Serialize)]
 #[serde(untagged)]
 enum RunFakeUnion {
     LayerRun(LayerRun),
@@ -150,27 +186,335 @@ impl From<RunFakeUnion> for Vec<Run> {
     }
 }
 
-// TODO: move away from Box<dyn Error> once the errortype of toml::from_str is undrestood
-pub fn parse_toml(toml_data: &str) -> Result<Vec<Run>, Box<dyn Error>> {
-    use std::collections::HashMap;
-    // TODO:
-    // 1. Validate that there are no duplicate runs
-    // 2. Validate that proof.starts_with("https://")
-    // 3. Validate that layer/act/fullgame runs contain one of each the required level/later/act runs
-    // 4. Validate that layer/act/fullgame runs time is the sum of it's level/later/act runs
-    // 5. Validate that layer/act/fullgame runs category is less or equally restrictive to it's level/later/act runs
-    // 6. Validate that submission_date.date.is_some()
-    // 7. Use MaybePatch
-    // 8? Validate that !(runner.contains("http://") || runner.contains("https://"))
-    // 9. Validate that there are no tracks that could not be ran during the specified patch or
-    //    submission date
-    // 10. Validate that super::Level::Custom() is defined in toml.remove("custom-levels")
-    Ok(
-        toml::from_str::<HashMap<String, Vec<RunFakeUnion>>>(toml_data)?
-            .remove("runs")
-            .ok_or("Missing \"runs\"")?
-            .into_iter()
-            .flat_map(Into::<Vec<Run>>::into)
-            .collect(),
-    )
+impl RunFakeUnion {
+    /// Deserializes a run as the kind of run its segments key points to, so that errors name the
+    /// field at fault rather than no kind of run matching
+    fn from_table(run: &toml::Table) -> Result<Self, String> {
+        fn parse<T: for<'de> Deserialize<'de>>(run: &toml::Table) -> Result<T, String> {
+            // through the text, as datetimes only deserialize from a document
+            toml::from_str(&toml::to_string(run).map_err(|e| e.to_string())?)
+                .map_err(|e| e.message().to_owned())
+        }
+        if run.contains_key("levels") {
+            parse(run).map(Self::LayerRun)
+        } else if run.contains_key("layers") {
+            parse(run).map(Self::ActRun)
+        } else if run.contains_key("acts") {
+            parse(run).map(Self::Fullgame)
+        } else {
+            parse(run).map(Self::IndividualLevelRun)
+        }
+    }
+
+    /// Deserializes the runs one by one, so that errors point at the run at fault
+    fn from_spanned(runs: &[Spanned<toml::Table>], toml_data: &str) -> Result<Vec<Self>, Error> {
+        runs.iter()
+            .map(|run| {
+                Self::from_table(run.get_ref()).map_err(|message| {
+                    Error::syntax("run_data.toml", &message, Some(run.span()), toml_data)
+                })
+            })
+            .collect()
+    }
+
+    /// Inverse of the flattening, nests a run over its already nested segments
+    fn nest(run: &Run, segments: Vec<Self>) -> Option<Self> {
+        let Run {
+            runner,
+            igt_ms,
+            category,
+            submission_date,
+            difficulty,
+            patch_release_date,
+            proof,
+            ..
+        } = run.clone();
+        Some(match &run.track {
+            Track::Level(track) => Self::IndividualLevelRun(IndividualLevelRun {
+                runner,
+                track: track.clone(),
+                igt_ms,
+                category,
+                submission_date,
+                difficulty,
+                patch_release_date,
+                proof,
+            }),
+            Track::Layer(track) => Self::LayerRun(LayerRun {
+                runner,
+                track: track.clone(),
+                igt_ms,
+                category,
+                submission_date,
+                difficulty,
+                patch_release_date,
+                proof,
+                levels: segments
+                    .into_iter()
+                    .map(|s| match s {
+                        Self::IndividualLevelRun(v) => Some(v),
+                        Self::LayerRun(_) | Self::ActRun(_) | Self::Fullgame(_) => None,
+                    })
+                    .collect::<Option<_>>()?,
+            }),
+            Track::Act(track) => {
+                let mut layers = Vec::new();
+                let mut prime = None;
+                for segment in segments {
+                    match segment {
+                        Self::LayerRun(v) if prime.is_none() => layers.push(v),
+                        Self::IndividualLevelRun(v) if prime.is_none() => prime = Some(v),
+                        Self::LayerRun(_)
+                        | Self::IndividualLevelRun(_)
+                        | Self::ActRun(_)
+                        | Self::Fullgame(_) => return None,
+                    }
+                }
+                Self::ActRun(ActRun {
+                    runner,
+                    track: track.clone(),
+                    igt_ms,
+                    category,
+                    submission_date,
+                    difficulty,
+                    patch_release_date,
+                    proof,
+                    layers,
+                    prime,
+                })
+            }
+            Track::Fullgame => Self::Fullgame(FullgameRun {
+                runner,
+                igt_ms,
+                category,
+                submission_date,
+                difficulty,
+                patch_release_date,
+                proof,
+                acts: segments
+                    .into_iter()
+                    .map(|s| match s {
+                        Self::ActRun(v) => Some(v),
+                        Self::IndividualLevelRun(_) | Self::LayerRun(_) | Self::Fullgame(_) => None,
+                    })
+                    .collect::<Option<_>>()?,
+            }),
+        })
+    }
+}
+
+impl Error {
+    pub(super) fn from_toml(error: &de::Error, file: &str, toml_data: &str) -> Self {
+        Self::syntax(file, error.message(), error.span(), toml_data)
+    }
+
+    /// Syntax error in `file`, positioned at the start of `span` inside `toml_data`
+    fn syntax(file: &str, message: &str, span: Option<Range<usize>>, toml_data: &str) -> Self {
+        let position = span.map(|span| {
+            let before = toml_data.get(..span.start).unwrap_or(toml_data);
+            Position {
+                line: before.matches('\n').count() + 1,
+                column: before
+                    .rsplit_once('\n')
+                    .map_or(before, |(_, line)| line)
+                    .chars()
+                    .count()
+                    + 1,
+            }
+        });
+        Self::Syntax {
+            file: file.to_owned(),
+            message: message.to_owned(),
+            position,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct RunDataTable {
+    #[serde(default)]
+    validation: ValidationConfig,
+    #[serde(default, rename = "custom-levels")]
+    custom_levels: CustomLevels,
+    runs: Option<Vec<Spanned<toml::Table>>>,
+}
+
+#[derive(Serialize)]
+struct RunDataTableRef<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    validation: Option<&'a ValidationConfig>,
+    #[serde(
+        rename = "custom-levels",
+        skip_serializing_if = "CustomLevels::is_empty"
+    )]
+    custom_levels: &'a CustomLevels,
+    runs: &'a [RunFakeUnion],
+}
+
+/// Nests a run over the runs of its segments, taking them out of `segment_runs`
+fn nest_tree(
+    run: &Run,
+    segment_runs: &mut BTreeMap<RunId, Vec<&Run>>,
+) -> Result<RunFakeUnion, WriteError> {
+    let missing_segments = || WriteError::MissingSegments {
+        runner: run.runner.clone(),
+        track: run.track.clone(),
+    };
+    let segments = run.track.segments();
+    let mut children = segment_runs.remove(&run.id()).unwrap_or_default();
+    children.sort_by_key(|c| segments.iter().position(|s| *s == c.track));
+    if !children.iter().map(|c| &c.track).eq(&segments) {
+        return Err(missing_segments());
+    }
+    let children = children
+        .into_iter()
+        .map(|c| nest_tree(c, segment_runs))
+        .collect::<Result<_, _>>()?;
+    RunFakeUnion::nest(run, children).ok_or_else(missing_segments)
+}
+
+/// Nests the runs back as written, following their parent references, over the file `toml_data`
+///
+/// # Errors
+/// Errors if the runs reference missing runs, if `toml_data` is malformed or if TOML cannot
+/// represent the runs
+pub fn write_toml(toml_data: &str, data: &RunData) -> Result<String, WriteError> {
+    let original = toml_data
+        .parse::<DocumentMut>()
+        .map_err(|e| WriteError::Toml(e.to_string()))?;
+    let mut segment_runs = BTreeMap::<RunId, Vec<&Run>>::new();
+    for run in &data.runs {
+        if let Some(parent) = run.parent {
+            segment_runs.entry(parent).or_default().push(run);
+        }
+    }
+    let nested = data
+        .runs
+        .iter()
+        .filter(|r| r.parent.is_none())
+        .map(|r| nest_tree(r, &mut segment_runs))
+        .collect::<Result<Vec<_>, _>>()?;
+    if let Some(orphan) = segment_runs.into_values().flatten().next() {
+        return Err(WriteError::MissingParent {
+            runner: orphan.runner.clone(),
+            track: orphan.track.clone(),
+        });
+    }
+    let validation = (original.contains_key("validation")
+        || data.validation != ValidationConfig::default())
+    .then_some(&data.validation);
+    let mut written = toml::to_string(&RunDataTableRef {
+        validation,
+        custom_levels: &data.custom_levels,
+        runs: &nested,
+    })
+    .map_err(|e| WriteError::Toml(e.to_string()))?
+    .parse::<DocumentMut>()
+    .map_err(|e| WriteError::Toml(e.to_string()))?;
+    keep_style(original.as_table(), written.as_table_mut());
+    group_igt_digits(written.as_table_mut());
+    written.set_trailing(original.trailing().clone());
+    Ok(written.to_string())
+}
+
+/// Carries the comments over from the file being overwritten, along with the way values are
+/// written when they did not change
+///
+/// Tables are matched by key and arrays of tables by position.
+fn keep_style(original: &Table, written: &mut Table) {
+    written.decor_mut().clone_from(original.decor());
+    for (mut key, item) in written.iter_mut() {
+        let Some((original_key, original_item)) = original.get_key_value(key.get()) else {
+            continue;
+        };
+        key.leaf_decor_mut().clone_from(original_key.leaf_decor());
+        match (original_item, item) {
+            (Item::Table(original), Item::Table(written)) => keep_style(original, written),
+            (Item::ArrayOfTables(original), Item::ArrayOfTables(written)) => {
+                for (original, written) in original.iter().zip(written.iter_mut()) {
+                    keep_style(original, written);
+                }
+            }
+            (Item::Value(original), Item::Value(written)) => {
+                if same_value(original, written) {
+                    written.clone_from(original);
+                } else {
+                    written.decor_mut().clone_from(original.decor());
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Whether the values are equal, however they are written
+fn same_value(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::String(a), Value::String(b)) => a.value() == b.value(),
+        (Value::Integer(a), Value::Integer(b)) => a.value() == b.value(),
+        (Value::Boolean(a), Value::Boolean(b)) => a.value() == b.value(),
+        (Value::Datetime(a), Value::Datetime(b)) => a.value() == b.value(),
+        (Value::Array(a), Value::Array(b)) => {
+            a.len() == b.len() && a.iter().zip(b).all(|(a, b)| same_value(a, b))
+        }
+        _ => false,
+    }
+}
+
+/// Splits the in-game times into groups of 3 digits, as they are written by hand
+fn group_igt_digits(table: &mut Table) {
+    for (key, item) in table.iter_mut() {
+        match item {
+            Item::Value(Value::Integer(ms)) if key.get() == "igt_ms" => {
+                let digits = ms.value().to_string();
+                let mut grouped = String::new();
+                for (idx, digit) in digits.chars().enumerate() {
+                    if idx > 0 && (digits.len() - idx) % 3 == 0 {
+                        grouped.push('_');
+                    }
+                    grouped.push(digit);
+                }
+                if let Ok(mut value) = grouped.parse::<Value>() {
+                    value.decor_mut().clone_from(ms.decor());
+                    *item = Item::Value(value);
+                }
+            }
+            Item::Table(table) => group_igt_digits(table),
+            Item::ArrayOfTables(tables) => tables.iter_mut().for_each(group_igt_digits),
+            Item::None | Item::Value(_) => {}
+        }
+    }
+}
+
+pub fn parse_toml(toml_data: &str, runners: &Runners) -> Result<RunData, Error> {
+    let data = toml::from_str::<RunDataTable>(toml_data)
+        .map_err(|e| Error::from_toml(&e, "run_data.toml", toml_data))?;
+    let runs = RunFakeUnion::from_spanned(&data.runs.ok_or(Error::MissingRuns)?, toml_data)?;
+    let custom_level_errors = data.custom_levels.errors();
+    if !custom_level_errors.is_empty() {
+        return Err(Error::InvalidCustomLevels(custom_level_errors));
+    }
+    validation::validate(&runs, data.validation, runners, &data.custom_levels)
+        .map_err(Error::Invalid)?;
+    let mut runs = runs
+        .into_iter()
+        .flat_map(Into::<Vec<Run>>::into)
+        .collect::<Vec<_>>();
+    // ids depend on the runner, so the parent references have to follow the resolution
+    let mut ids = BTreeMap::new();
+    for run in &mut runs {
+        let id = run.id();
+        if let Some(runner) = runners.resolve(&run.runner) {
+            run.runner.clone_from(&runner.id);
+        }
+        let _ = ids.insert(id, run.id());
+    }
+    for run in &mut runs {
+        run.parent = run.parent.and_then(|parent| ids.get(&parent).copied());
+    }
+    Ok(RunData {
+        runs,
+        custom_levels: data.custom_levels,
+        validation: data.validation,
+    })
 }
@@ -0,0 +1,595 @@
+//! Semantic checks performed on the deserialized runs, before they get flattened
+use super::{ActRun, FullgameRun, IndividualLevelRun, LayerRun, RunFakeUnion};
+use crate::gamedata::{
+    Category, CustomLevels, Datetime, Deserialize, Difficulty, Level, Patch, Run, Runners,
+    Serialize, Track,
+};
+use std::{
+    collections::{btree_map::Entry, BTreeMap},
+    fmt,
+};
+
+/// Position of a run inside the TOML document, e.g. `runs[3].levels[0]` or `runs[0].prime`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Location(Vec<(String, Option<usize>)>);
+
+impl Location {
+    fn new(key: &str, idx: usize) -> Self {
+        Self(vec![(key.to_owned(), Some(idx))])
+    }
+
+    fn join(&self, key: &str, idx: Option<usize>) -> Self {
+        let mut path = self.0.clone();
+        path.push((key.to_owned(), idx));
+        Self(path)
+    }
+}
+
+impl fmt::Display for Location {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, (key, idx)) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ".")?;
+            }
+            write!(f, "{key}")?;
+            if let Some(idx) = idx {
+                write!(f, "[{idx}]")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Violation {
+    /// Same runner, track, time, difficulty, patch and submission date as the run at `Location`
+    Duplicate(Location),
+    /// Proof link not starting with `https://`
+    InsecureProof(String),
+    /// Submission date lacking the date part, e.g. `submission_date = 12:00:00`
+    MissingSubmissionDate,
+    /// Runner name containing a link
+    LinkAsRunner,
+    /// Runner name matching no runner in `runners.toml`
+    UnknownRunner,
+    /// Patch matching neither a version label nor a release date in `assets/patches.toml`
+    UnknownPatch(String),
+    /// Track added to the game after the run's patch, or not added by any known patch
+    UnavailableTrack(Option<Patch>),
+    /// Track neither of the game nor declared in `custom-levels`, e.g. a misspelled level
+    UnknownTrack(String),
+    /// Submission date preceding the release of the run's patch
+    SubmittedBeforePatch(Patch),
+    /// Aggregate run time differing from the sum of its segments by more than the tolerance
+    SegmentSumMismatch { igt_ms: u32, segments_igt_ms: u64 },
+    /// Aggregate run lacking some of the segments its track is made of
+    MissingSegments(Vec<Track>),
+    /// Aggregate run containing segments its track is not made of, or containing them twice
+    ExtraSegments(Vec<Track>),
+    /// Aggregate run containing the right segments in the wrong order
+    MisorderedSegments(Vec<Track>),
+    /// Aggregate run containing segments whose category does not satisfy the run's
+    LooserSegments(Category, Vec<Track>),
+}
+
+impl fmt::Display for Violation {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Duplicate(original) => write!(f, "is a duplicate of the run at {original}"),
+            Self::InsecureProof(proof) => write!(f, "has a non-https proof: \"{proof}\""),
+            Self::MissingSubmissionDate => write!(f, "has a submission date without a date"),
+            Self::LinkAsRunner => write!(f, "has a link as the runner name"),
+            Self::UnknownRunner => write!(f, "has a runner missing from runners.toml"),
+            Self::UnknownPatch(label) => write!(f, "is on the unknown patch \"{label}\""),
+            Self::UnavailableTrack(Some(added)) => {
+                write!(f, "is on a track that was only added in {added}")
+            }
+            Self::UnavailableTrack(None) => write!(f, "is on a track no known patch added"),
+            Self::UnknownTrack(name) => write!(
+                f,
+                "is on the unknown track \"{name}\", neither of the game nor declared in custom-levels"
+            ),
+            Self::SubmittedBeforePatch(patch) => write!(
+                f,
+                "was submitted before {patch} was released{}",
+                patch
+                    .release_date()
+                    .map(|date| format!(" on {date}"))
+                    .unwrap_or_default()
+            ),
+            Self::SegmentSumMismatch {
+                igt_ms,
+                segments_igt_ms,
+            } => write!(
+                f,
+                "has an igt of {igt_ms}ms but its segments sum up to {segments_igt_ms}ms ({}ms {})",
+                segments_igt_ms.abs_diff(u64::from(*igt_ms)),
+                if u64::from(*igt_ms) > *segments_igt_ms {
+                    "over"
+                } else {
+                    "under"
+                }
+            ),
+            Self::MissingSegments(tracks) => {
+                write!(f, "is missing the segments {}", tracks_to_string(tracks))
+            }
+            Self::ExtraSegments(tracks) => {
+                write!(f, "has the extra segments {}", tracks_to_string(tracks))
+            }
+            Self::MisorderedSegments(expected) => write!(
+                f,
+                "has its segments out of order, expected {}",
+                tracks_to_string(expected)
+            ),
+            Self::LooserSegments(category, tracks) => write!(
+                f,
+                "is a {category} run but its segments {} are not",
+                tracks_to_string(tracks)
+            ),
+        }
+    }
+}
+
+fn tracks_to_string(tracks: &[Track]) -> String {
+    tracks
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationError {
+    pub runner: String,
+    pub track: Track,
+    pub location: Location,
+    pub violation: Violation,
+}
+
+impl fmt::Display for ValidationError {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}: {}'s {} run {}",
+            self.location, self.runner, self.track, self.violation
+        )
+    }
+}
+
+/// A segment entry along with the key it is stored under and its index if stored in an array
+type Segment<'a> = (&'static str, Option<usize>, &'a dyn Node);
+
+/// Common view over the different kinds of run entries
+trait Node {
+    /// The run described by the entry itself, without its segments
+    fn run(&self) -> Run;
+    /// The segments of the entry in order, if it is an aggregate run
+    fn segments(&self) -> Option<Vec<Segment>>;
+}
+
+/// Indexes the entries of a segment array
+fn segment_array<'a, T: Node>(key: &'static str, runs: &'a [T]) -> Vec<Segment<'a>> {
+    runs.iter()
+        .enumerate()
+        .map(|(idx, r)| -> Segment { (key, Some(idx), r) })
+        .collect()
+}
+
+impl Node for IndividualLevelRun {
+    fn run(&self) -> Run {
+        Run {
+            runner: self.runner.clone(),
+            track: Track::Level(self.track.clone()),
+            igt_ms: self.igt_ms,
+            category: self.category,
+            submission_date: self.submission_date,
+            difficulty: self.difficulty,
+            patch_release_date: self.patch_release_date.clone(),
+            proof: self.proof.clone(),
+            parent: None,
+        }
+    }
+
+    fn segments(&self) -> Option<Vec<Segment>> {
+        None
+    }
+}
+
+impl Node for LayerRun {
+    fn run(&self) -> Run {
+        Run {
+            runner: self.runner.clone(),
+            track: Track::Layer(self.track.clone()),
+            igt_ms: self.igt_ms,
+            category: self.category,
+            submission_date: self.submission_date,
+            difficulty: self.difficulty,
+            patch_release_date: self.patch_release_date.clone(),
+            proof: self.proof.clone(),
+            parent: None,
+        }
+    }
+
+    fn segments(&self) -> Option<Vec<Segment>> {
+        Some(segment_array("levels", &self.levels))
+    }
+}
+
+impl Node for ActRun {
+    fn run(&self) -> Run {
+        Run {
+            runner: self.runner.clone(),
+            track: Track::Act(self.track.clone()),
+            igt_ms: self.igt_ms,
+            category: self.category,
+            submission_date: self.submission_date,
+            difficulty: self.difficulty,
+            patch_release_date: self.patch_release_date.clone(),
+            proof: self.proof.clone(),
+            parent: None,
+        }
+    }
+
+    fn segments(&self) -> Option<Vec<Segment>> {
+        let mut segments = segment_array("layers", &self.layers);
+        segments.extend(self.prime.iter().map(|r| -> Segment { ("prime", None, r) }));
+        Some(segments)
+    }
+}
+
+impl Node for FullgameRun {
+    fn run(&self) -> Run {
+        Run {
+            runner: self.runner.clone(),
+            track: Track::Fullgame,
+            igt_ms: self.igt_ms,
+            category: self.category,
+            submission_date: self.submission_date,
+            difficulty: self.difficulty,
+            patch_release_date: self.patch_release_date.clone(),
+            proof: self.proof.clone(),
+            parent: None,
+        }
+    }
+
+    fn segments(&self) -> Option<Vec<Segment>> {
+        Some(segment_array("acts", &self.acts))
+    }
+}
+
+impl RunFakeUnion {
+    fn as_node(&self) -> &dyn Node {
+        match self {
+            Self::LayerRun(v) => v,
+            Self::ActRun(v) => v,
+            Self::Fullgame(v) => v,
+            Self::IndividualLevelRun(v) => v,
+        }
+    }
+}
+
+/// Knobs for the checks, read from the `[validation]` table
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidationConfig {
+    /// Allowed difference between an aggregate run and the sum of its segments, as loading screens
+    /// may round the in-game time
+    #[serde(default)]
+    igt_sum_tolerance_ms: u32,
+}
+
+/// The board and the fields it sorts by, two runs sharing all of them are duplicates
+type DuplicateKey = (Track, Category, u32, Difficulty, Patch, Datetime, String);
+
+struct Validator<'a> {
+    config: ValidationConfig,
+    runners: &'a Runners,
+    custom_levels: &'a CustomLevels,
+    seen: BTreeMap<DuplicateKey, Location>,
+    errors: Vec<ValidationError>,
+}
+
+impl Validator<'_> {
+    fn visit(&mut self, node: &dyn Node, location: &Location) {
+        let run = node.run();
+        let mut report = |violation| {
+            self.errors.push(ValidationError {
+                runner: run.runner.clone(),
+                track: run.track.clone(),
+                location: location.clone(),
+                violation,
+            });
+        };
+
+        if !run.proof.starts_with("https://") {
+            report(Violation::InsecureProof(run.proof.clone()));
+        }
+        if run.submission_date.date.is_none() {
+            report(Violation::MissingSubmissionDate);
+        }
+        if let Track::Level(Level::Custom(id)) = &run.track {
+            if self.custom_levels.get(id).is_none() {
+                report(Violation::UnknownTrack(id.clone()));
+            }
+        }
+        if let Patch::Unknown(label) = &run.patch_release_date {
+            report(Violation::UnknownPatch(label.clone()));
+        } else if !matches!(run.track, Track::Level(Level::Custom(_))) {
+            match Patch::introducing(&run.track) {
+                Some(added) if added <= run.patch_release_date => {}
+                added => report(Violation::UnavailableTrack(added)),
+            }
+        }
+        if run
+            .patch_release_date
+            .release_date()
+            .is_some_and(|release| run.submission_date < release)
+        {
+            report(Violation::SubmittedBeforePatch(
+                run.patch_release_date.clone(),
+            ));
+        }
+        if run.runner.contains("http://") || run.runner.contains("https://") {
+            report(Violation::LinkAsRunner);
+        }
+        let runner = self.runners.resolve(&run.runner);
+        if runner.is_none() {
+            report(Violation::UnknownRunner);
+        }
+        match self.seen.entry((
+            run.track.clone(),
+            run.category,
+            run.igt_ms,
+            run.difficulty,
+            run.patch_release_date.clone(),
+            run.submission_date,
+            runner.map_or_else(|| run.runner.clone(), |r| r.id.clone()),
+        )) {
+            Entry::Occupied(original) => report(Violation::Duplicate(original.get().clone())),
+            Entry::Vacant(slot) => {
+                let _ = slot.insert(location.clone());
+            }
+        }
+
+        if let Some(segments) = node.segments() {
+            let segment_runs = segments.iter().map(|(_, _, s)| s.run()).collect::<Vec<_>>();
+            for violation in segment_violations(&run, segment_runs, self.config) {
+                report(violation);
+            }
+            for (key, idx, segment) in segments {
+                self.visit(segment, &location.join(key, idx));
+            }
+        }
+    }
+}
+
+/// Checks the segments of an aggregate run against the run
+fn segment_violations(
+    run: &Run,
+    segment_runs: Vec<Run>,
+    config: ValidationConfig,
+) -> Vec<Violation> {
+    let mut violations = Vec::new();
+    let segments_igt_ms = segment_runs
+        .iter()
+        .map(|s| u64::from(s.igt_ms))
+        .sum::<u64>();
+    if segments_igt_ms.abs_diff(u64::from(run.igt_ms)) > u64::from(config.igt_sum_tolerance_ms) {
+        violations.push(Violation::SegmentSumMismatch {
+            igt_ms: run.igt_ms,
+            segments_igt_ms,
+        });
+    }
+    let looser = segment_runs
+        .iter()
+        .filter(|s| !s.category.satisfies(run.category))
+        .map(|s| s.track.clone())
+        .collect::<Vec<_>>();
+    if !looser.is_empty() {
+        violations.push(Violation::LooserSegments(run.category, looser));
+    }
+    let found = segment_runs
+        .into_iter()
+        .map(|s| s.track)
+        .collect::<Vec<_>>();
+    let expected = run.track.segments();
+    if found != expected {
+        let mut extra = found;
+        let missing = expected
+            .iter()
+            .filter(|t| {
+                extra
+                    .iter()
+                    .position(|e| e == *t)
+                    .map(|idx| extra.remove(idx))
+                    .is_none()
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+        match (missing.is_empty(), extra.is_empty()) {
+            (true, true) => violations.push(Violation::MisorderedSegments(expected)),
+            (false, true) => violations.push(Violation::MissingSegments(missing)),
+            (true, false) => violations.push(Violation::ExtraSegments(extra)),
+            (false, false) => {
+                violations.push(Violation::MissingSegments(missing));
+                violations.push(Violation::ExtraSegments(extra));
+            }
+        }
+    }
+    violations
+}
+
+/// Checks every run and its segments, collecting all the violations found
+pub(super) fn validate(
+    runs: &[RunFakeUnion],
+    config: ValidationConfig,
+    runners: &Runners,
+    custom_levels: &CustomLevels,
+) -> Result<(), Vec<ValidationError>> {
+    let mut validator = Validator {
+        config,
+        runners,
+        custom_levels,
+        seen: BTreeMap::new(),
+        errors: Vec::new(),
+    };
+    for (idx, run) in runs.iter().enumerate() {
+        validator.visit(run.as_node(), &Location::new("runs", idx));
+    }
+    if validator.errors.is_empty() {
+        Ok(())
+    } else {
+        Err(validator.errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        super::{RunDataTable, RunFakeUnion},
+        validate, Location, Violation,
+    };
+    use crate::gamedata::{parse_runners, Act, Level, Track};
+
+    const RUNNERS: &str = r#"
+[[runners]]
+id = "someone"
+name = "Someone"
+"#;
+
+    /// A 0-1 run breaking no rule unless given a bad runner, submission date or proof
+    fn level_run(runner: &str, submission_date: &str, proof: &str) -> String {
+        format!(
+            r#"
+[[runs]]
+runner = "{runner}"
+track = "0-1"
+igt_ms = 30_000
+category = "Any"
+submission_date = {submission_date}
+difficulty = "Violent"
+patch_release_date = "EA"
+proof = "{proof}"
+"#
+        )
+    }
+
+    fn valid_run() -> String {
+        level_run("Someone", "2023-01-01", "https://youtu.be/proof")
+    }
+
+    fn violations(toml_data: &str) -> Vec<Violation> {
+        let data = toml::from_str::<RunDataTable>(toml_data).expect("the test runs should parse");
+        let runners = parse_runners(RUNNERS).expect("the test runners should parse");
+        let runs = RunFakeUnion::from_spanned(&data.runs.unwrap_or_default(), toml_data)
+            .expect("the test runs should be well formed");
+        validate(&runs, data.validation, &runners, &data.custom_levels)
+            .err()
+            .unwrap_or_default()
+            .into_iter()
+            .map(|e| e.violation)
+            .collect()
+    }
+
+    #[test]
+    fn accepts_valid_run() {
+        assert_eq!(violations(&valid_run()), []);
+    }
+
+    #[test]
+    fn detects_duplicate() {
+        assert_eq!(
+            violations(&[valid_run(), valid_run()].concat()),
+            [Violation::Duplicate(Location::new("runs", 0))]
+        );
+    }
+
+    #[test]
+    fn detects_duplicate_across_runner_names() {
+        let renamed = level_run("someone", "2023-01-01", "https://youtu.be/proof");
+        assert_eq!(
+            violations(&[valid_run(), renamed].concat()),
+            [Violation::Duplicate(Location::new("runs", 0))]
+        );
+    }
+
+    #[test]
+    fn accepts_same_time_in_other_category() {
+        let other = valid_run().replace("category = \"Any\"", "category = \"P\"");
+        assert_eq!(violations(&[valid_run(), other].concat()), []);
+    }
+
+    #[test]
+    fn detects_insecure_proof() {
+        let run = level_run("Someone", "2023-01-01", "http://youtu.be/proof");
+        assert_eq!(
+            violations(&run),
+            [Violation::InsecureProof("http://youtu.be/proof".to_owned())]
+        );
+    }
+
+    #[test]
+    fn detects_unknown_track() {
+        let run = valid_run().replace("track = \"0-1\"", "track = \"6-3\"");
+        assert_eq!(
+            violations(&run),
+            [Violation::UnknownTrack("6-3".to_owned())]
+        );
+    }
+
+    #[test]
+    fn accepts_declared_custom_level() {
+        let level = r#"
+[[custom-levels]]
+id = "6-3"
+name = "Not Quite 6-3"
+author = "Someone"
+link = "https://example.com"
+version = "1.0"
+"#;
+        let run = valid_run().replace("track = \"0-1\"", "track = \"6-3\"");
+        assert_eq!(violations(&[level, &run].concat()), []);
+    }
+
+    #[test]
+    fn detects_missing_submission_date() {
+        let run = level_run("Someone", "12:00:00", "https://youtu.be/proof");
+        assert!(violations(&run).contains(&Violation::MissingSubmissionDate));
+    }
+
+    #[test]
+    fn requires_prime_level_closing_act() {
+        let run = r#"
+[[runs]]
+runner = "Someone"
+track = "Act I"
+igt_ms = 0
+category = "Any"
+submission_date = 2023-01-01
+difficulty = "Violent"
+patch_release_date = "P-1"
+proof = "https://youtu.be/proof"
+layers = []
+"#;
+        let mut expected = Act::I
+            .layers()
+            .iter()
+            .cloned()
+            .map(Track::Layer)
+            .collect::<Vec<_>>();
+        expected.push(Track::Level(Level::LP_1));
+        assert_eq!(violations(run), [Violation::MissingSegments(expected)]);
+    }
+
+    #[test]
+    fn detects_link_as_runner() {
+        let run = level_run(
+            "https://www.speedrun.com/users/Someone",
+            "2023-01-01",
+            "https://youtu.be/proof",
+        );
+        assert!(violations(&run).contains(&Violation::LinkAsRunner));
+    }
+}
@@ -0,0 +1,121 @@
+//! Fetches the run data at runtime, so that new runs do not require a rebuild
+use cfg_if::cfg_if;
+use leptos::logging::warn;
+
+/// Copies of the data from build time, used when the current ones cannot be fetched
+const EMBEDDED_RUN_DATA: &str = std::include_str!("../../assets/run_data.toml");
+const EMBEDDED_RUNNERS_DATA: &str = std::include_str!("../../assets/runners.toml");
+
+cfg_if! {
+if #[cfg(feature = "ssr")] {
+    /// Reads a file from the site root, which `cargo leptos` copies the assets into,
+    /// only reading it again once it gets modified
+    ///
+    /// The file system is accessed on the blocking thread pool, away from the actix workers.
+    async fn fetch(file: &'static str) -> Result<String, String> {
+        use actix_web::web;
+        use std::{
+            collections::BTreeMap,
+            env, fs,
+            path::Path,
+            sync::{Mutex, PoisonError},
+            time::SystemTime,
+        };
+        static CACHE: Mutex<BTreeMap<&str, (SystemTime, String)>> = Mutex::new(BTreeMap::new());
+
+        web::block(move || {
+            let site_root =
+                env::var("LEPTOS_SITE_ROOT").unwrap_or_else(|_| "target/site".to_owned());
+            let path = Path::new(&site_root).join(file);
+            let modified = fs::metadata(&path)
+                .and_then(|m| m.modified())
+                .map_err(|e| e.to_string())?;
+            let cached = CACHE
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .get(file)
+                .filter(|(cached, _)| *cached == modified)
+                .map(|(_, data)| data.clone());
+            if let Some(data) = cached {
+                return Ok(data);
+            }
+            let data = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+            *CACHE
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner)
+                .entry(file)
+                .or_insert_with(|| (modified, String::new())) = (modified, data.clone());
+            Ok(data)
+        })
+        .await
+        .map_err(|e| e.to_string())?
+    }
+} else if #[cfg(feature = "csr")] {
+    /// URL of a file trunk copied to the site root
+    ///
+    /// The root of the domain, except on ghpages where the site lives under the repository path
+    /// and only serves its index page, so that the page's directory is the site root.
+    fn site_url(file: &str) -> String {
+        if cfg!(feature = "ghpages") {
+            file.to_owned()
+        } else {
+            format!("/{file}")
+        }
+    }
+
+    /// Requests a file from the site root, as built by trunk, only once per page
+    #[allow(clippy::future_not_send)] // reason = "the browser is single threaded"
+    async fn fetch(file: &'static str) -> Result<String, String> {
+        use gloo_net::http::Request;
+        use std::{
+            collections::BTreeMap,
+            sync::{Mutex, PoisonError},
+        };
+        static CACHE: Mutex<BTreeMap<&str, String>> = Mutex::new(BTreeMap::new());
+
+        let cached = CACHE
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .get(file)
+            .cloned();
+        if let Some(data) = cached {
+            return Ok(data);
+        }
+        let response = Request::get(&site_url(file)).send().await.map_err(|e| e.to_string())?;
+        if !response.ok() {
+            return Err(format!("{file} responded with {}", response.status()));
+        }
+        let data = response.text().await.map_err(|e| e.to_string())?;
+        CACHE
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(file)
+            .or_default()
+            .clone_from(&data);
+        Ok(data)
+    }
+} else {
+    #[allow(clippy::unused_async)] // reason = "must match the signature of the other loaders"
+    async fn fetch(_file: &'static str) -> Result<String, String> {
+        Err("no loader for this build".to_owned())
+    }
+}
+}
+
+/// Fetches the run data, falling back to the copy embedded at build time
+#[cfg_attr(feature = "csr", allow(clippy::future_not_send))] // reason = "the browser is single threaded"
+pub async fn load_run_data() -> String {
+    fetch("run_data.toml").await.unwrap_or_else(|e| {
+        warn!("Failed to fetch the run data, using the embedded copy: {e}");
+        EMBEDDED_RUN_DATA.to_owned()
+    })
+}
+
+/// Fetches the runner registry, falling back to the copy embedded at build time
+#[cfg_attr(feature = "csr", allow(clippy::future_not_send))] // reason = "the browser is single threaded"
+pub async fn load_runners_data() -> String {
+    fetch("runners.toml").await.unwrap_or_else(|e| {
+        warn!("Failed to fetch the runner registry, using the embedded copy: {e}");
+        EMBEDDED_RUNNERS_DATA.to_owned()
+    })
+}
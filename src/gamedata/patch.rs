@@ -0,0 +1,157 @@
+use super::{Act, Datetime, Deserialize, Layer, Level, Serialize, Track};
+use serde::{Deserializer, Serializer};
+use std::{fmt, sync::OnceLock};
+
+#[derive(Debug, Deserialize)]
+struct PatchInfo {
+    version: String,
+    name: String,
+    release_date: Option<Datetime>,
+    #[serde(default)]
+    levels: Vec<Level>,
+    #[serde(default)]
+    layers: Vec<Layer>,
+    #[serde(default)]
+    acts: Vec<Act>,
+}
+
+#[derive(Deserialize)]
+struct PatchTable {
+    patches: Vec<PatchInfo>,
+}
+
+/// The known patches, from the oldest to the newest as the table lists them
+fn patches() -> &'static [PatchInfo] {
+    static PATCHES: OnceLock<Vec<PatchInfo>> = OnceLock::new();
+    PATCHES.get_or_init(|| {
+        #[allow(clippy::panic)]
+        // reason = "the table is embedded, so it is a bug if it is malformed"
+        toml::from_str::<PatchTable>(std::include_str!("../../assets/patches.toml"))
+            .unwrap_or_else(|e| panic!("Malformed patches.toml: {e}"))
+            .patches
+    })
+}
+
+/// A game update, written in the run data either as its version label or as its release date
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Patch {
+    /// Index in the patch table, so that patches order chronologically
+    Known(usize),
+    /// Neither a known version label nor a known release date
+    Unknown(String),
+}
+
+impl Patch {
+    /// Finds the patch with the given version label or release date
+    pub fn resolve(label: &str) -> Self {
+        let date = label.parse::<Datetime>().ok();
+        patches()
+            .iter()
+            .position(|p| p.version == label || (date.is_some() && p.release_date == date))
+            .map_or_else(|| Self::Unknown(label.to_owned()), Self::Known)
+    }
+
+    /// Every known patch, from the oldest to the newest
+    pub fn all() -> Vec<Self> {
+        (0..patches().len()).map(Self::Known).collect()
+    }
+
+    fn info(&self) -> Option<&'static PatchInfo> {
+        match self {
+            Self::Known(idx) => patches().get(*idx),
+            Self::Unknown(_) => None,
+        }
+    }
+
+    /// The patch that added the track to the game, if any known patch did
+    pub fn introducing(track: &Track) -> Option<Self> {
+        let added_by =
+            |adds: &dyn Fn(&PatchInfo) -> bool| patches().iter().position(adds).map(Self::Known);
+        match track {
+            Track::Fullgame => track
+                .segments()
+                .iter()
+                .map(Self::introducing)
+                .collect::<Option<Vec<_>>>()?
+                .into_iter()
+                .max(),
+            Track::Act(a) => added_by(&|p| p.acts.contains(a)),
+            Track::Layer(l) => added_by(&|p| p.layers.contains(l)),
+            Track::Level(l) => added_by(&|p| p.levels.contains(l)),
+        }
+    }
+
+    /// The label runs refer to the patch with
+    pub fn version(&self) -> &str {
+        match (self, self.info()) {
+            (_, Some(info)) => &info.version,
+            (Self::Unknown(label), None) => label,
+            (Self::Known(_), None) => "",
+        }
+    }
+
+    /// The release date, if sourced
+    pub fn release_date(&self) -> Option<Datetime> {
+        self.info().and_then(|p| p.release_date)
+    }
+}
+
+impl fmt::Display for Patch {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match (self, self.info()) {
+            (_, Some(info)) => write!(f, "{}", info.name),
+            (Self::Unknown(label), None) => write!(f, "{label}"),
+            (Self::Known(idx), None) => write!(f, "Patch #{idx}"),
+        }
+    }
+}
+
+impl Serialize for Patch {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.version())
+    }
+}
+
+impl<'de> Deserialize<'de> for Patch {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer).map(|label| Self::resolve(&label))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{patches, Patch, Track};
+
+    #[test]
+    fn release_dates_follow_the_table_order() {
+        let dates = patches()
+            .iter()
+            .filter_map(|p| p.release_date)
+            .collect::<Vec<_>>();
+        assert!(dates.windows(2).all(|w| w[0] < w[1]), "{dates:?}");
+    }
+
+    #[test]
+    fn every_track_is_added_by_a_patch() {
+        for track in Track::all() {
+            assert!(
+                Patch::introducing(&track).is_some(),
+                "no patch adds {track}"
+            );
+        }
+    }
+
+    #[test]
+    fn segments_are_added_with_or_before_their_track() {
+        for track in Track::all() {
+            let added = Patch::introducing(&track);
+            for segment in track.segments() {
+                assert!(
+                    Patch::introducing(&segment) <= added,
+                    "{segment} is added after {track}"
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,210 @@
+//! Registry of the runners, so that a runner keeps a single history across name changes
+use super::{Deserialize, Error, Serialize};
+use std::{collections::BTreeMap, fmt};
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Links {
+    pub speedrun: Option<String>,
+    pub youtube: Option<String>,
+    pub twitch: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub struct Runner {
+    /// Stable identifier, used by the runs once resolved and in URLs
+    pub id: String,
+    /// Name currently displayed
+    pub name: String,
+    /// Other names the runner goes by
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// Names the runner used to go by, which older runs may still use
+    #[serde(default)]
+    pub past_names: Vec<String>,
+    /// ISO 3166-1 alpha-2 country code, e.g. `it`
+    pub country: Option<String>,
+    #[serde(default)]
+    pub links: Links,
+}
+
+impl Runner {
+    /// Every name runs may refer to the runner by
+    fn names(&self) -> impl Iterator<Item = &String> {
+        [&self.id, &self.name]
+            .into_iter()
+            .chain(&self.aliases)
+            .chain(&self.past_names)
+    }
+
+    fn goes_by(&self, name: &str) -> bool {
+        self.names().any(|n| n.eq_ignore_ascii_case(name))
+    }
+
+    /// Flag emoji of the country, made of the regional indicator symbols of its code
+    pub fn flag(&self) -> Option<String> {
+        self.country
+            .as_deref()
+            .filter(|c| c.len() == 2 && c.chars().all(|c| c.is_ascii_alphabetic()))
+            .map(|c| {
+                c.to_ascii_uppercase()
+                    .chars()
+                    .filter_map(|c| char::from_u32(0x1F1E6 + u32::from(c) - u32::from('A')))
+                    .collect()
+            })
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Runners(Vec<Runner>);
+
+impl Runners {
+    pub fn get(&self, id: &str) -> Option<&Runner> {
+        self.0.iter().find(|r| r.id == id)
+    }
+
+    /// Finds the runner going by the name, ignoring case
+    pub fn resolve(&self, name: &str) -> Option<&Runner> {
+        self.get(name)
+            .or_else(|| self.0.iter().find(|r| r.goes_by(name)))
+    }
+
+    /// Display name of the runner, or the id itself for runners missing from the registry
+    pub fn name<'a>(&'a self, id: &'a str) -> &'a str {
+        self.get(id).map_or(id, |r| &r.name)
+    }
+
+    /// The names several runners go by, ignoring case, including ids shared by several runners
+    fn collisions(&self) -> Vec<NameCollision> {
+        let mut claims = BTreeMap::<String, (&str, Vec<(usize, &str)>)>::new();
+        for (idx, runner) in self.0.iter().enumerate() {
+            for name in runner.names() {
+                let (_, claimants) = claims
+                    .entry(name.to_ascii_lowercase())
+                    .or_insert_with(|| (name, Vec::new()));
+                // a runner may list the same name twice, e.g. as its id and as its name
+                if claimants
+                    .last()
+                    .is_none_or(|&(claimant, _)| claimant != idx)
+                {
+                    claimants.push((idx, &runner.id));
+                }
+            }
+        }
+        claims
+            .into_values()
+            .filter(|(_, claimants)| claimants.len() > 1)
+            .map(|(name, claimants)| NameCollision {
+                name: name.to_owned(),
+                runners: claimants.into_iter().map(|(_, id)| id.to_owned()).collect(),
+            })
+            .collect()
+    }
+}
+
+/// Name going by several runners of the registry, so that runs using it cannot be told apart
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NameCollision {
+    pub name: String,
+    /// Ids of the runners going by the name, in registry order
+    pub runners: Vec<String>,
+}
+
+impl fmt::Display for NameCollision {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "\"{}\" is used by the runners {}",
+            self.name,
+            self.runners.join(", ")
+        )
+    }
+}
+
+#[derive(Deserialize)]
+struct RunnersTable {
+    #[serde(default)]
+    runners: Vec<Runner>,
+}
+
+/// Parses the runner registry
+///
+/// # Errors
+/// Errors on parsing errors or if several runners go by the same name
+pub fn parse_runners(toml_data: &str) -> Result<Runners, Error> {
+    let runners = toml::from_str::<RunnersTable>(toml_data)
+        .map(|table| Runners(table.runners))
+        .map_err(|e| Error::from_toml(&e, "runners.toml", toml_data))?;
+    let collisions = runners.collisions();
+    if collisions.is_empty() {
+        Ok(runners)
+    } else {
+        Err(Error::AmbiguousRunners(collisions))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_runners, Error, NameCollision};
+
+    fn collisions(toml_data: &str) -> Vec<NameCollision> {
+        match parse_runners(toml_data) {
+            Err(Error::AmbiguousRunners(collisions)) => collisions,
+            other => panic!("expected ambiguous runners, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn accepts_runner_repeating_own_name() {
+        let runners = r#"
+[[runners]]
+id = "someone"
+name = "Someone"
+aliases = ["SOMEONE"]
+"#;
+        assert!(parse_runners(runners).is_ok());
+    }
+
+    #[test]
+    fn rejects_duplicate_ids() {
+        let runners = r#"
+[[runners]]
+id = "someone"
+name = "Someone"
+
+[[runners]]
+id = "someone"
+name = "Someone Else"
+"#;
+        assert_eq!(
+            collisions(runners),
+            [NameCollision {
+                name: "someone".to_owned(),
+                runners: vec!["someone".to_owned(), "someone".to_owned()],
+            }]
+        );
+    }
+
+    #[test]
+    fn rejects_name_shared_across_runners() {
+        let runners = r#"
+[[runners]]
+id = "someone"
+name = "Someone"
+past-names = ["Shared"]
+
+[[runners]]
+id = "other"
+name = "Other"
+aliases = ["shared"]
+"#;
+        assert_eq!(
+            collisions(runners),
+            [NameCollision {
+                name: "Shared".to_owned(),
+                runners: vec!["someone".to_owned(), "other".to_owned()],
+            }]
+        );
+    }
+}
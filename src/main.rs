@@ -1,12 +1,13 @@
 #[cfg(feature = "ssr")]
 #[allow(clippy::absolute_paths)] // reason = "Conditional compilation"
+#[allow(clippy::literal_string_with_formatting_args)] // reason = "actix path patterns use braces"
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
     use actix_files::Files;
     use actix_web::{web, App, HttpServer};
     use leptos::{get_configuration, logging::log};
     use leptos_actix::{generate_route_list, LeptosRoutes};
-    use uknd::app::App;
+    use uknd::{api::v1, app::App};
     #[allow(clippy::panic)] // reason = "Server should crash when failing to configure"
     let conf = get_configuration(None)
         .await
@@ -21,6 +22,7 @@ async fn main() -> std::io::Result<()> {
         let site_root = &leptos_options.site_root;
 
         App::new()
+            .service(v1::service())
             .route("/api/{tail:.*}", leptos_actix::handle_server_fns())
             // serve JS/WASM/CSS from `pkg`
             .service(Files::new("/pkg", format!("{site_root}/pkg")))
@@ -51,6 +53,7 @@ async fn favicon(
 }
 
 #[cfg(not(any(feature = "ssr", feature = "csr")))]
+#[allow(clippy::missing_const_for_fn)] // reason = "main cannot be const"
 pub fn main() {
     // no client-side main function
     // unless we want this to work with e.g., Trunk for pure client-side testing